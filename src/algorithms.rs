@@ -0,0 +1,391 @@
+//! Graph algorithms over GraphData: ordering, cycles, components and paths.
+//!
+//! Edges are followed from `source` to `target` as written, and node ids are the
+//! path-prefixed ids used in [`GraphData`]. Nothing here touches the DOM.
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::fmt;
+
+use crate::{edge_renderer::EdgeData, graph_data::GraphData, graph_query::GraphIndex};
+
+/// Returned when an algorithm needs a DAG but the graph has a cycle
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cycle<'a> {
+    /// Nodes along the cycle. Each has an edge to the next, the last one back to the first.
+    pub nodes: Vec<&'a str>,
+}
+
+impl fmt::Display for Cycle<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Graph has a cycle: ")?;
+        for node in &self.nodes {
+            write!(f, "{node} -> ")?;
+        }
+        write!(f, "{}", self.nodes.first().unwrap_or(&""))
+    }
+}
+
+impl std::error::Error for Cycle<'_> {}
+
+/// A path through the graph
+#[derive(Clone, Debug, PartialEq)]
+pub struct Path<'a> {
+    /// Nodes from start to end, both included
+    pub nodes: Vec<&'a str>,
+    /// The edge taken between each pair of consecutive nodes
+    pub edges: Vec<&'a EdgeData>,
+    /// Sum of the edge weights, or the number of edges for unweighted searches
+    pub length: f64,
+}
+
+/// Weight of an edge read from `attribute`. Missing or non-numeric values count as 1.
+pub fn edge_weight(edge: &EdgeData, attribute: &str) -> f64 {
+    edge.attributes
+        .get(attribute)
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .filter(|weight| weight.is_finite())
+        .unwrap_or(1.0)
+}
+
+/// Order nodes so every edge goes from an earlier to a later node.
+/// Ties keep document order.
+pub fn topological_sort(graph: &GraphData) -> Result<Vec<&str>, Cycle<'_>> {
+    let index = graph.index();
+    let nodes = node_ids(graph);
+
+    // Kahn's algorithm, parallel edges count once
+    let mut in_degree: HashMap<&str, usize> = nodes
+        .iter()
+        .map(|&id| (id, index.predecessors(id).len()))
+        .collect();
+    let mut queue: VecDeque<&str> = nodes
+        .iter()
+        .copied()
+        .filter(|id| in_degree[id] == 0)
+        .collect();
+
+    let mut order = Vec::with_capacity(nodes.len());
+    while let Some(node) = queue.pop_front() {
+        order.push(node);
+        for next in index.successors(node) {
+            if let Some(degree) = in_degree.get_mut(next) {
+                *degree -= 1;
+                if *degree == 0 {
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    if order.len() < nodes.len() {
+        return Err(find_cycle(graph).unwrap_or(Cycle { nodes: Vec::new() }));
+    }
+    Ok(order)
+}
+
+/// Find one cycle in the graph, if there is any. Self loops count as cycles.
+pub fn find_cycle(graph: &GraphData) -> Option<Cycle<'_>> {
+    #[derive(PartialEq)]
+    enum Visit {
+        InProgress,
+        Done,
+    }
+
+    let index = graph.index();
+    let mut visits: HashMap<&str, Visit> = HashMap::new();
+
+    for start in node_ids(graph) {
+        if visits.contains_key(start) {
+            continue;
+        }
+        visits.insert(start, Visit::InProgress);
+        // Depth-first, each entry holds a node, its successors and the next one to visit
+        let mut stack = vec![(start, index.successors(start), 0)];
+        while let Some(top) = stack.last_mut() {
+            let Some(&next) = top.1.get(top.2) else {
+                visits.insert(top.0, Visit::Done);
+                stack.pop();
+                continue;
+            };
+            top.2 += 1;
+            match visits.get(next) {
+                None => {
+                    visits.insert(next, Visit::InProgress);
+                    stack.push((next, index.successors(next), 0));
+                }
+                Some(Visit::InProgress) => {
+                    // `next` is on the stack, the cycle is everything above it
+                    let from = stack.iter().position(|(node, ..)| *node == next)?;
+                    let nodes = stack[from..].iter().map(|(node, ..)| *node).collect();
+                    return Some(Cycle { nodes });
+                }
+                Some(Visit::Done) => {}
+            }
+        }
+    }
+    None
+}
+
+/// Whether the graph has no cycles
+pub fn is_acyclic(graph: &GraphData) -> bool {
+    find_cycle(graph).is_none()
+}
+
+/// Group nodes into strongly connected components: nodes that can all reach each other.
+///
+/// Every node is in exactly one component, components are listed so that edges
+/// between them only point to later components.
+pub fn strongly_connected_components(graph: &GraphData) -> Vec<Vec<&str>> {
+    let index = graph.index();
+
+    // Kosaraju: first order nodes by when their depth-first visit finishes...
+    let mut visited = HashSet::new();
+    let mut finished = Vec::new();
+    for start in node_ids(graph) {
+        if !visited.insert(start) {
+            continue;
+        }
+        let mut stack = vec![(start, index.successors(start), 0)];
+        while let Some(top) = stack.last_mut() {
+            match top.1.get(top.2).copied() {
+                Some(next) => {
+                    top.2 += 1;
+                    if visited.insert(next) {
+                        stack.push((next, index.successors(next), 0));
+                    }
+                }
+                None => {
+                    finished.push(top.0);
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    // ...then walk edges backwards from the last finished nodes
+    let mut assigned = HashSet::new();
+    let mut components = Vec::new();
+    for &root in finished.iter().rev() {
+        if !assigned.insert(root) {
+            continue;
+        }
+        let mut component = vec![root];
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            for previous in index.predecessors(node) {
+                if assigned.insert(previous) {
+                    component.push(previous);
+                    stack.push(previous);
+                }
+            }
+        }
+        components.push(component);
+    }
+    components
+}
+
+/// Rank of each node: the number of edges on the longest path reaching it.
+/// Nodes without incoming edges have rank 0.
+pub fn ranks(graph: &GraphData) -> Result<HashMap<&str, usize>, Cycle<'_>> {
+    let index = graph.index();
+    let mut ranks = HashMap::new();
+    for node in topological_sort(graph)? {
+        let rank = index
+            .predecessors(node)
+            .iter()
+            .filter_map(|previous| ranks.get(previous))
+            .map(|rank| rank + 1)
+            .max()
+            .unwrap_or(0);
+        ranks.insert(node, rank);
+    }
+    Ok(ranks)
+}
+
+/// Path from `from` to `to` with the fewest edges (breadth-first search)
+pub fn shortest_path<'a>(graph: &'a GraphData, from: &str, to: &str) -> Option<Path<'a>> {
+    let index = graph.index();
+    let from = index.node(from)?.id.as_str();
+    let mut previous: HashMap<&str, &EdgeData> = HashMap::new();
+    let mut seen = HashSet::from([from]);
+    let mut queue = VecDeque::from([from]);
+
+    while let Some(node) = queue.pop_front() {
+        if node == to {
+            let path = trace_back(from, node, &previous);
+            let length = path.edges.len() as f64;
+            return Some(Path { length, ..path });
+        }
+        for edge in index.out_edges(node) {
+            if seen.insert(edge.target.as_str()) {
+                previous.insert(edge.target.as_str(), edge);
+                queue.push_back(edge.target.as_str());
+            }
+        }
+    }
+    None
+}
+
+/// Cheapest path from `from` to `to` (Dijkstra), edge weights read from the
+/// `weight_attribute` edge attribute with [`edge_weight`]. Negative weights count as 0.
+pub fn weighted_shortest_path<'a>(
+    graph: &'a GraphData,
+    from: &str,
+    to: &str,
+    weight_attribute: &str,
+) -> Option<Path<'a>> {
+    let index = graph.index();
+    let from = index.node(from)?.id.as_str();
+    let mut distances: HashMap<&str, f64> = HashMap::from([(from, 0.0)]);
+    let mut previous: HashMap<&str, &EdgeData> = HashMap::new();
+    let mut done = HashSet::new();
+    let mut queue = BinaryHeap::from([Reverse(Candidate(0.0, from))]);
+
+    while let Some(Reverse(Candidate(distance, node))) = queue.pop() {
+        if !done.insert(node) {
+            continue;
+        }
+        if node == to {
+            let path = trace_back(from, node, &previous);
+            return Some(Path {
+                length: distance,
+                ..path
+            });
+        }
+        for edge in index.out_edges(node) {
+            let candidate = distance + edge_weight(edge, weight_attribute).max(0.0);
+            let target = edge.target.as_str();
+            if distances.get(target).is_none_or(|&known| candidate < known) {
+                distances.insert(target, candidate);
+                previous.insert(target, edge);
+                queue.push(Reverse(Candidate(candidate, target)));
+            }
+        }
+    }
+    None
+}
+
+/// Heaviest path from `from` to `to`, edge weights read from `weight_attribute`.
+/// Only defined for DAGs, `Ok(None)` when `to` can't be reached from `from`.
+pub fn longest_path<'a>(
+    graph: &'a GraphData,
+    from: &str,
+    to: &str,
+    weight_attribute: &str,
+) -> Result<Option<Path<'a>>, Cycle<'a>> {
+    let index = graph.index();
+    let Some(from) = index.node(from).map(|node| node.id.as_str()) else {
+        return Ok(None);
+    };
+    let (distances, previous) = longest_distances(graph, &index, Some(from), weight_attribute)?;
+
+    Ok(distances.get(to).map(|&length| {
+        let path = trace_back(from, to, &previous);
+        Path { length, ..path }
+    }))
+}
+
+/// The heaviest path anywhere in a DAG (its critical path), edge weights read
+/// from `weight_attribute`. `Ok(None)` for an empty graph.
+pub fn critical_path<'a>(
+    graph: &'a GraphData,
+    weight_attribute: &str,
+) -> Result<Option<Path<'a>>, Cycle<'a>> {
+    let index = graph.index();
+    let (distances, previous) = longest_distances(graph, &index, None, weight_attribute)?;
+
+    // The first node in document order wins ties
+    let mut end: Option<(&str, f64)> = None;
+    for node in node_ids(graph) {
+        let distance = distances[node];
+        if end.is_none_or(|(_, best)| distance > best) {
+            end = Some((node, distance));
+        }
+    }
+
+    Ok(end.map(|(end, length)| {
+        // Walk back until a node that wasn't reached through an edge
+        let mut start = end;
+        while let Some(edge) = previous.get(start) {
+            start = &edge.source;
+        }
+        let path = trace_back(start, end, &previous);
+        Path { length, ..path }
+    }))
+}
+
+// Longest distance to every node reachable from `from`, or to every node when `from`
+// is None, along with the edge used to get there
+#[allow(clippy::type_complexity)]
+fn longest_distances<'a>(
+    graph: &'a GraphData,
+    index: &GraphIndex<'a>,
+    from: Option<&'a str>,
+    weight_attribute: &str,
+) -> Result<(HashMap<&'a str, f64>, HashMap<&'a str, &'a EdgeData>), Cycle<'a>> {
+    let order = topological_sort(graph)?;
+    let mut distances: HashMap<&str, f64> = match from {
+        Some(from) => HashMap::from([(from, 0.0)]),
+        None => order.iter().map(|&node| (node, 0.0)).collect(),
+    };
+    let mut previous: HashMap<&str, &EdgeData> = HashMap::new();
+
+    for node in order {
+        let Some(&distance) = distances.get(node) else {
+            continue;
+        };
+        for edge in index.out_edges(node) {
+            let candidate = distance + edge_weight(edge, weight_attribute);
+            let target = edge.target.as_str();
+            if distances.get(target).is_none_or(|&known| candidate > known) {
+                distances.insert(target, candidate);
+                previous.insert(target, edge);
+            }
+        }
+    }
+    Ok((distances, previous))
+}
+
+// Rebuild the path ending at `to` from the edge used to reach each node
+fn trace_back<'a>(from: &'a str, to: &str, previous: &HashMap<&str, &'a EdgeData>) -> Path<'a> {
+    let mut edges = Vec::new();
+    let mut node = to;
+    while node != from {
+        let Some(edge) = previous.get(node) else {
+            break;
+        };
+        edges.push(*edge);
+        node = &edge.source;
+    }
+    edges.reverse();
+
+    let mut nodes = vec![from];
+    nodes.extend(edges.iter().map(|edge| edge.target.as_str()));
+    Path {
+        nodes,
+        edges,
+        length: 0.0,
+    }
+}
+
+fn node_ids(graph: &GraphData) -> Vec<&str> {
+    graph.all_nodes().map(|node| node.id.as_str()).collect()
+}
+
+// Distance and node in the Dijkstra queue, ordered by distance
+#[derive(PartialEq)]
+struct Candidate<'a>(f64, &'a str);
+
+impl Eq for Candidate<'_> {}
+
+impl PartialOrd for Candidate<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0).then_with(|| self.1.cmp(other.1))
+    }
+}
@@ -0,0 +1,181 @@
+//! Ordered DOT attribute map shared by nodes, edges and (sub)graphs.
+use crate::preprocess::{restore_backslashes, restore_html};
+
+/// A single `key=value` pair as it appeared in the DOT source.
+#[derive(Clone, Debug, PartialEq)]
+struct Attribute {
+    key: String,
+    value: String,
+    /// Whether the value was written as an HTML-like string (`<...>`)
+    html: bool,
+}
+
+/// Ordered map of DOT attributes.
+///
+/// Keys keep the position of their first declaration, re-declaring a key
+/// overwrites its value in place, just like Graphviz does.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct Attributes {
+    entries: Vec<Attribute>,
+}
+
+impl Attributes {
+    /// Create an empty attribute map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the (unquoted) value of an attribute
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|a| a.key == key)
+            .map(|a| a.value.as_str())
+    }
+
+    /// Whether the attribute is present
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.entries.iter().any(|a| a.key == key)
+    }
+
+    /// Whether the attribute value is an HTML-like string (`label=<...>`)
+    pub fn is_html(&self, key: &str) -> bool {
+        self.entries.iter().any(|a| a.key == key && a.html)
+    }
+
+    /// Insert or overwrite a plain string attribute
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.set(key.into(), value.into(), false);
+    }
+
+    /// Insert or overwrite an HTML-like attribute. The value excludes the outer `<` `>`.
+    pub fn insert_html(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.set(key.into(), value.into(), true);
+    }
+
+    /// Insert an attribute exactly as written in DOT source, removing quotes
+    /// and escapes, or the outer angle brackets of HTML-like strings.
+    pub fn insert_raw(&mut self, key: &str, raw_value: &str) {
+        let key = unquote(key);
+        match raw_value
+            .strip_prefix('<')
+            .and_then(|v| v.strip_suffix('>'))
+        {
+            Some(html) => self.insert_html(key, restore_html(html)),
+            None => self.insert(key, unquote(raw_value)),
+        }
+    }
+
+    /// Remove an attribute, returning its value
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let index = self.entries.iter().position(|a| a.key == key)?;
+        Some(self.entries.remove(index).value)
+    }
+
+    /// Merge `other` into `self`; values in `other` win
+    pub fn merge(&mut self, other: &Attributes) {
+        for attr in &other.entries {
+            self.set(attr.key.clone(), attr.value.clone(), attr.html);
+        }
+    }
+
+    /// Iterate over `(key, value)` pairs in declaration order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries
+            .iter()
+            .map(|a| (a.key.as_str(), a.value.as_str()))
+    }
+
+    /// Number of attributes
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there are no attributes
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn set(&mut self, key: String, value: String, html: bool) {
+        match self.entries.iter_mut().find(|a| a.key == key) {
+            Some(existing) => {
+                existing.value = value;
+                existing.html = html;
+            }
+            None => self.entries.push(Attribute { key, value, html }),
+        }
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Attributes {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut attrs = Attributes::new();
+        for (key, value) in iter {
+            attrs.insert(key, value);
+        }
+        attrs
+    }
+}
+
+/// Remove the surrounding quotes of a DOT identifier and unescape `\"`.
+/// Like Graphviz, a `\\` pair is kept as it is, so `"a\\"` ends after the pair.
+/// The backslashes right before the closing quote or an escaped quote are the
+/// exception: [`quote`] doubles those, so they are halved again here.
+pub(crate) fn unquote(raw: &str) -> String {
+    match raw.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(inner) => {
+            let inner = restore_backslashes(inner);
+            let mut out = String::with_capacity(inner.len());
+            let mut chars = inner.chars().peekable();
+            while let Some(c) = chars.next() {
+                if c != '\\' {
+                    out.push(c);
+                    continue;
+                }
+                let mut run = 1;
+                while chars.next_if_eq(&'\\').is_some() {
+                    run += 1;
+                }
+                match chars.peek() {
+                    // The last backslash escapes the quote
+                    Some('"') if run % 2 == 1 => {
+                        chars.next();
+                        out.push_str(&"\\".repeat(run / 2));
+                        out.push('"');
+                    }
+                    Some('"') | None => out.push_str(&"\\".repeat(run.div_ceil(2))),
+                    Some(_) => out.push_str(&"\\".repeat(run)),
+                }
+            }
+            out
+        }
+        None => raw.to_string(),
+    }
+}
+
+/// Quote a DOT string, escaping embedded quotes. The inverse of [`unquote`].
+///
+/// DOT has no way to write a lone backslash at the end of a string or before a
+/// quote, so the backslashes there are doubled, which Graphviz renders as the
+/// same text.
+pub(crate) fn quote(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => {
+                let mut run = 1;
+                while chars.next_if_eq(&'\\').is_some() {
+                    run += 1;
+                }
+                let doubled = matches!(chars.peek(), None | Some('"'));
+                out.push_str(&"\\".repeat(if doubled { run * 2 } else { run }));
+            }
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -1,7 +1,9 @@
 //! Generic approach where any component can become a DOT node renderer by implementing a trait
 use crate::{
-    edge_renderer::EdgeArena,
-    graph_data::{GraphData, NodeData},
+    edge_renderer::{node_dom_id, EdgeArena},
+    edge_routing::EdgeRouting,
+    error::ParseError,
+    graph_data::{parse_graph, GraphData, NodeData},
 };
 use dioxus::prelude::*;
 
@@ -23,6 +25,9 @@ pub struct DotGraphProps<R: DotNodeRenderer + Clone + PartialEq + 'static> {
     /// Optional class for the container
     #[props(!optional)]
     pub class: Option<String>,
+
+    /// Prefix for the DOM ids of the graph, its clusters and its nodes, see [`EdgeArena`]
+    pub namespace: Option<String>,
 }
 
 /// Component to render a DOT graph with custom node rendering
@@ -30,26 +35,20 @@ pub struct DotGraphProps<R: DotNodeRenderer + Clone + PartialEq + 'static> {
 pub fn DotGraph<R: DotNodeRenderer + Clone + PartialEq + 'static>(
     props: DotGraphProps<R>,
 ) -> Element {
-    // Parse the DOT string
-    let graph_result = dot_parser::ast::Graph::<(&str, &str)>::try_from(props.dot.as_str());
-
-    // Handle parsing errors
-    if let Err(err) = &graph_result {
-        return rsx! {
-            div {
-                class: "p-4 bg-red-100 text-red-700 rounded",
-                "Error parsing DOT: {err}"
-            }
-        };
-    }
-
-    // Convert to our graph data format
-    let graph = GraphData::from_ast(&graph_result.unwrap());
+    // Parse the DOT string into our graph data format
+    let graph = match parse_graph(&props.dot) {
+        Ok(graph) => graph,
+        // Handle parsing errors
+        Err(err) => {
+            return rsx! {
+                ParseErrorView { error: err, source: props.dot.clone() }
+            };
+        }
+    };
 
     rsx! {
         div {
             class: "relative {props.class.clone().unwrap_or_default()}",
-            id: "graph-container",
 
             // Graph title if available
             if let Some(label) = &graph.label {
@@ -60,8 +59,10 @@ pub fn DotGraph<R: DotNodeRenderer + Clone + PartialEq + 'static>(
             }
 
             EdgeArena {
+                namespace: props.namespace.clone(),
                 edges: graph.edges.clone(),
                 node_ids: graph.nodes.iter().map(|n| n.id.clone()).collect(),
+                routing: EdgeRouting::from_attributes(&graph.attributes),
                 GraphContent {
                     graph: graph,
                     renderer: props.renderer.clone(),
@@ -72,6 +73,42 @@ pub fn DotGraph<R: DotNodeRenderer + Clone + PartialEq + 'static>(
     }
 }
 
+/// Shows a DOT parse error with the offending source line underlined
+#[component]
+pub fn ParseErrorView(error: ParseError, source: String) -> Element {
+    let span = error.span();
+    let line: Vec<char> = span.source_line(&source).chars().collect();
+    let column = span.column.saturating_sub(1).min(line.len());
+    let marked_end = (column + span.underline_len(&source)).min(line.len());
+
+    let before: String = line[..column].iter().collect();
+    let marked: String = line[column..marked_end].iter().collect();
+    let after: String = line[marked_end..].iter().collect();
+    // Errors at the end of a line still need something visible to underline
+    let marked = if marked.is_empty() {
+        " ".to_string()
+    } else {
+        marked
+    };
+
+    rsx! {
+        div {
+            class: "p-4 bg-red-100 text-red-700 rounded",
+            "Error parsing DOT: {error}"
+            pre {
+                class: "mt-2 font-mono text-sm whitespace-pre overflow-x-auto",
+                span { class: "text-red-400 select-none", "{span.line} | " }
+                "{before}"
+                span {
+                    class: "underline decoration-wavy decoration-red-600 bg-red-200",
+                    "{marked}"
+                }
+                "{after}"
+            }
+        }
+    }
+}
+
 #[derive(Clone, Props, PartialEq)]
 struct GraphContentProps<R: DotNodeRenderer + Clone + PartialEq + 'static> {
     graph: GraphData,
@@ -84,7 +121,8 @@ struct GraphContentProps<R: DotNodeRenderer + Clone + PartialEq + 'static> {
 fn GraphContent<R: DotNodeRenderer + Clone + PartialEq + 'static>(
     props: GraphContentProps<R>,
 ) -> Element {
-    let mut is_collapsed = use_signal(|| props.collapsed.unwrap_or(true));
+    // Grouping subgraphs have no header to toggle, so they are never collapsed
+    let mut is_collapsed = use_signal(|| props.collapsed.unwrap_or(props.graph.is_cluster));
 
     // Calculate the nesting level to alternate flex direction
     // Count the number of hyphens to determine nesting level
@@ -108,7 +146,7 @@ fn GraphContent<R: DotNodeRenderer + Clone + PartialEq + 'static>(
         _ => "border-solid",
     };
 
-    let container_class = if props.graph.id.starts_with("cluster_") {
+    let container_class = if props.graph.is_cluster {
         let base_class =
             "relative p-4 m-2 bg-slate-50 border-2 {style_class} border-slate-300 rounded-lg";
         if is_collapsed() {
@@ -127,16 +165,18 @@ fn GraphContent<R: DotNodeRenderer + Clone + PartialEq + 'static>(
         if is_collapsed() { " [+] " } else { " [-] " }
     );
 
-    // Main container for the graph or subgraph
+    // Main container for the graph or subgraph. Its DOM id is scoped to the arena like the
+    // nodes', the graph's own id stays available as `data-graph-id`.
     rsx! {
         div {
-            id: "{props.graph.id}",
+            id: node_dom_id(&props.graph.id),
+            "data-graph-id": "{props.graph.id}",
             class: "{container_class}",
-            "data-subgraph": if props.graph.id.starts_with("cluster_") { "true" } else { "false" },
+            "data-subgraph": if props.graph.is_cluster { "true" } else { "false" },
 
             // Clickable label for collapsing/expanding subgraphs
             if props.graph.label.is_some() {
-                if props.graph.id.starts_with("cluster_") {
+                if props.graph.is_cluster {
                     div {
                         class: "absolute -top-3 left-4 px-2 bg-slate-50 text-sm font-bold cursor-pointer select-none whitespace-nowrap z-10",
                         onclick: toggle_collapse,
@@ -166,7 +206,7 @@ fn GraphContent<R: DotNodeRenderer + Clone + PartialEq + 'static>(
                     {props.graph.nodes.iter().map(|node| {
                         rsx! {
                             div {
-                                id: "{node.id}",
+                                id: node_dom_id(&node.id),
                                 "data-node": "true",
                                 // Use w-fit to minimize width but ensure minimum readability
                                 class: "w-fit h-fit",
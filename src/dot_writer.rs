@@ -0,0 +1,234 @@
+//! Serialize GraphData back to DOT text
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::{
+    attributes::{quote, Attributes},
+    edge_dir::EdgeDir,
+    edge_port::EdgePort,
+    edge_renderer::EdgeData,
+    graph_data::{GraphData, NodeData},
+    rankdir::RankDir,
+};
+
+const INDENT: &str = "    ";
+
+impl GraphData {
+    /// Write the graph as a DOT document.
+    ///
+    /// The output parses back into the same GraphData with [`parse_graph`](crate::graph_data::parse_graph).
+    /// Node ids are written without their subgraph path prefix, and every attribute
+    /// is written explicitly, so no `node [...]`/`edge [...]` defaults are needed.
+    pub fn to_dot(&self) -> String {
+        let mut original_ids = HashMap::new();
+        collect_original_ids(self, "", &mut original_ids);
+
+        let mut out = String::new();
+        if self.strict {
+            out.push_str("strict ");
+        }
+        let _ = writeln!(out, "{} {} {{", self.kind.keyword(), id_to_dot(&self.id));
+        write_body(&mut out, self, "", 1);
+
+        for edge in &self.edges {
+            let source = original_ids.get(&edge.source).unwrap_or(&edge.source);
+            let target = original_ids.get(&edge.target).unwrap_or(&edge.target);
+            let _ = writeln!(
+                out,
+                "{INDENT}{}{} {} {}{}{};",
+                id_to_dot(source),
+                port_to_dot(edge.source_port.as_ref()),
+                self.kind.edge_op(),
+                id_to_dot(target),
+                port_to_dot(edge.target_port.as_ref()),
+                attr_list(&edge_attributes(edge, self))
+            );
+        }
+
+        out.push_str("}\n");
+        out
+    }
+}
+
+// Write the nodes, subgraphs and graph attributes of a (sub)graph
+fn write_body(out: &mut String, graph: &GraphData, path_prefix: &str, depth: usize) {
+    let indent = INDENT.repeat(depth);
+    let attributes = graph_attributes(graph);
+
+    // Subgraphs inherit graph attributes declared before them (except `label`), so
+    // attributes that some nested subgraph doesn't have are written after the subgraphs.
+    let (leading, trailing): (Vec<_>, Vec<_>) = attributes
+        .iter()
+        .partition(|(key, _)| *key == "label" || all_subgraphs_have(graph, key));
+
+    for (key, _) in leading {
+        write_graph_attribute(out, &indent, &attributes, key);
+    }
+
+    for node in &graph.nodes {
+        let id = strip_prefix(&node.id, path_prefix);
+        let _ = writeln!(
+            out,
+            "{indent}{}{};",
+            id_to_dot(id),
+            attr_list(&node_attributes(node))
+        );
+    }
+
+    for subgraph in &graph.subgraphs {
+        let prefix = if path_prefix.is_empty() {
+            subgraph.id.clone()
+        } else {
+            format!("{path_prefix}-{}", subgraph.id)
+        };
+        let _ = writeln!(out, "{indent}subgraph {} {{", id_to_dot(&subgraph.id));
+        write_body(out, subgraph, &prefix, depth + 1);
+        let _ = writeln!(out, "{indent}}}");
+    }
+
+    for (key, _) in trailing {
+        write_graph_attribute(out, &indent, &attributes, key);
+    }
+}
+
+fn write_graph_attribute(out: &mut String, indent: &str, attributes: &Attributes, key: &str) {
+    let value = attributes.get(key).unwrap_or_default();
+    let _ = writeln!(
+        out,
+        "{indent}{}={};",
+        id_to_dot(key),
+        value_to_dot(attributes, key, value)
+    );
+}
+
+// Graph attributes with the typed fields applied on top, so edits to them are kept
+fn graph_attributes(graph: &GraphData) -> Attributes {
+    let mut attributes = graph.attributes.clone();
+    set_or_remove(&mut attributes, "label", graph.label.as_deref());
+    set_or_remove(&mut attributes, "style", graph.style.as_deref());
+    if graph.direction != RankDir::default() || attributes.contains_key("rankdir") {
+        attributes.insert("rankdir", graph.direction.to_str());
+    }
+    attributes
+}
+
+fn node_attributes(node: &NodeData) -> Attributes {
+    let mut attributes = node.attributes.clone();
+    set_or_remove(&mut attributes, "label", node.label.as_deref());
+    attributes
+}
+
+fn edge_attributes(edge: &EdgeData, graph: &GraphData) -> Attributes {
+    let mut attributes = edge.attributes.clone();
+    set_or_remove(&mut attributes, "label", edge.label.as_deref());
+    if edge.dir != EdgeDir::default_for(graph.kind) || attributes.contains_key("dir") {
+        attributes.insert("dir", edge.dir.to_str());
+    }
+    attributes
+}
+
+// Keep HTML-like values HTML-like when the field still holds the same text
+fn set_or_remove(attributes: &mut Attributes, key: &str, value: Option<&str>) {
+    match value {
+        Some(value) if attributes.get(key) == Some(value) => {}
+        Some(value) => attributes.insert(key, value),
+        None => {
+            attributes.remove(key);
+        }
+    }
+}
+
+// Whether every subgraph below `graph` declares `key` itself
+fn all_subgraphs_have(graph: &GraphData, key: &str) -> bool {
+    graph
+        .subgraphs
+        .iter()
+        .all(|sg| sg.attributes.contains_key(key) && all_subgraphs_have(sg, key))
+}
+
+// Map path-prefixed node ids back to the ids written in DOT
+fn collect_original_ids(
+    graph: &GraphData,
+    path_prefix: &str,
+    original_ids: &mut HashMap<String, String>,
+) {
+    for node in &graph.nodes {
+        original_ids.insert(
+            node.id.clone(),
+            strip_prefix(&node.id, path_prefix).to_string(),
+        );
+    }
+    for subgraph in &graph.subgraphs {
+        let prefix = if path_prefix.is_empty() {
+            subgraph.id.clone()
+        } else {
+            format!("{path_prefix}-{}", subgraph.id)
+        };
+        collect_original_ids(subgraph, &prefix, original_ids);
+    }
+}
+
+fn strip_prefix<'a>(id: &'a str, path_prefix: &str) -> &'a str {
+    if path_prefix.is_empty() {
+        return id;
+    }
+    id.strip_prefix(path_prefix)
+        .and_then(|rest| rest.strip_prefix('-'))
+        .unwrap_or(id)
+}
+
+// `:port:compass` suffix of an edge endpoint
+fn port_to_dot(port: Option<&EdgePort>) -> String {
+    let Some(port) = port else {
+        return String::new();
+    };
+    let mut out = String::new();
+    if let Some(name) = &port.name {
+        let _ = write!(out, ":{}", id_to_dot(name));
+    }
+    if let Some(compass) = port.compass {
+        let _ = write!(out, ":{}", compass.to_str());
+    }
+    out
+}
+
+fn attr_list(attributes: &Attributes) -> String {
+    if attributes.is_empty() {
+        return String::new();
+    }
+    let items: Vec<String> = attributes
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "{}={}",
+                id_to_dot(key),
+                value_to_dot(attributes, key, value)
+            )
+        })
+        .collect();
+    format!(" [{}]", items.join(", "))
+}
+
+fn value_to_dot(attributes: &Attributes, key: &str, value: &str) -> String {
+    if attributes.is_html(key) {
+        format!("<{value}>")
+    } else {
+        quote(value)
+    }
+}
+
+// Write an id bare when it is a plain DOT identifier, quoted otherwise
+fn id_to_dot(id: &str) -> String {
+    const KEYWORDS: [&str; 6] = ["node", "edge", "graph", "digraph", "subgraph", "strict"];
+    let mut chars = id.chars();
+    let is_plain = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(id));
+    if is_plain {
+        id.to_string()
+    } else {
+        quote(id)
+    }
+}
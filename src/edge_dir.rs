@@ -0,0 +1,53 @@
+use crate::graph_kind::GraphKind;
+
+/// EdgeDir represents where arrowheads are drawn on an edge.
+/// It corresponds to the 'dir' attribute in DOT language.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum EdgeDir {
+    #[default]
+    Forward, // Arrowhead at the target (default for digraphs)
+    Back, // Arrowhead at the source
+    Both, // Arrowheads at both ends
+    None, // Plain connector (default for undirected graphs)
+}
+
+impl EdgeDir {
+    // Default direction for edges of the given graph kind
+    pub const fn default_for(kind: GraphKind) -> Self {
+        match kind {
+            GraphKind::Directed => EdgeDir::Forward,
+            GraphKind::Undirected => EdgeDir::None,
+        }
+    }
+
+    // Parse from string slice, falling back to the graph kind default
+    pub fn from_slice(s: &str, kind: GraphKind) -> Self {
+        match s.trim_matches('"') {
+            "forward" => EdgeDir::Forward,
+            "back" => EdgeDir::Back,
+            "both" => EdgeDir::Both,
+            "none" => EdgeDir::None,
+            _ => Self::default_for(kind),
+        }
+    }
+
+    // Convert to string for DOT output
+    pub const fn to_str(self) -> &'static str {
+        match self {
+            EdgeDir::Forward => "forward",
+            EdgeDir::Back => "back",
+            EdgeDir::Both => "both",
+            EdgeDir::None => "none",
+        }
+    }
+
+    /// Whether an arrowhead is drawn at the target end
+    pub const fn has_end_arrow(self) -> bool {
+        matches!(self, EdgeDir::Forward | EdgeDir::Both)
+    }
+
+    /// Whether an arrowhead is drawn at the source end
+    pub const fn has_start_arrow(self) -> bool {
+        matches!(self, EdgeDir::Back | EdgeDir::Both)
+    }
+}
@@ -0,0 +1,133 @@
+use dot_parser::ast;
+
+use crate::attributes::unquote;
+
+/// CompassPoint is the side or corner of a node an edge attaches to,
+/// as in `a:ne -> b:w` in DOT language.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompassPoint {
+    N,
+    NE,
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+    C,          // Center, the edge is clipped at the node boundary as usual
+    Underscore, // `_`, any side
+}
+
+impl CompassPoint {
+    // Parse from string slice
+    pub fn from_slice(s: &str) -> Option<Self> {
+        match s.trim_matches('"') {
+            "n" => Some(CompassPoint::N),
+            "ne" => Some(CompassPoint::NE),
+            "e" => Some(CompassPoint::E),
+            "se" => Some(CompassPoint::SE),
+            "s" => Some(CompassPoint::S),
+            "sw" => Some(CompassPoint::SW),
+            "w" => Some(CompassPoint::W),
+            "nw" => Some(CompassPoint::NW),
+            "c" => Some(CompassPoint::C),
+            "_" => Some(CompassPoint::Underscore),
+            _ => None,
+        }
+    }
+
+    // Convert to string for DOT output
+    pub const fn to_str(self) -> &'static str {
+        match self {
+            CompassPoint::N => "n",
+            CompassPoint::NE => "ne",
+            CompassPoint::E => "e",
+            CompassPoint::SE => "se",
+            CompassPoint::S => "s",
+            CompassPoint::SW => "sw",
+            CompassPoint::W => "w",
+            CompassPoint::NW => "nw",
+            CompassPoint::C => "c",
+            CompassPoint::Underscore => "_",
+        }
+    }
+
+    /// Where the edge attaches, as fractions of the box width and height from its
+    /// top-left corner. `None` for `c` and `_`, which leave the choice to the router.
+    pub const fn anchor(self) -> Option<(f64, f64)> {
+        match self {
+            CompassPoint::N => Some((0.5, 0.0)),
+            CompassPoint::NE => Some((1.0, 0.0)),
+            CompassPoint::E => Some((1.0, 0.5)),
+            CompassPoint::SE => Some((1.0, 1.0)),
+            CompassPoint::S => Some((0.5, 1.0)),
+            CompassPoint::SW => Some((0.0, 1.0)),
+            CompassPoint::W => Some((0.0, 0.5)),
+            CompassPoint::NW => Some((0.0, 0.0)),
+            CompassPoint::C | CompassPoint::Underscore => None,
+        }
+    }
+
+    fn from_ast(compass: ast::CompassPt) -> Self {
+        match compass {
+            ast::CompassPt::N => CompassPoint::N,
+            ast::CompassPt::NE => CompassPoint::NE,
+            ast::CompassPt::E => CompassPoint::E,
+            ast::CompassPt::SE => CompassPoint::SE,
+            ast::CompassPt::S => CompassPoint::S,
+            ast::CompassPt::SW => CompassPoint::SW,
+            ast::CompassPt::W => CompassPoint::W,
+            ast::CompassPt::NW => CompassPoint::NW,
+            ast::CompassPt::C => CompassPoint::C,
+            ast::CompassPt::Underscore => CompassPoint::Underscore,
+        }
+    }
+}
+
+/// EdgePort is the `:port:compass` part of an edge endpoint.
+/// The port name refers to an element inside the node with a matching `data-port` attribute.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct EdgePort {
+    pub name: Option<String>,
+    pub compass: Option<CompassPoint>,
+}
+
+impl EdgePort {
+    // Build from the dot-parser port. A lone compass name (`a:ne`) is a compass point,
+    // like Graphviz does for nodes without a field of that name. Ports quoted by the
+    // preprocessor (`a:"p:ne"`) are split back into name and compass point.
+    pub(crate) fn from_ast(port: &ast::Port) -> Self {
+        match port {
+            ast::Port::ID(name, Some(compass)) => EdgePort {
+                name: Some(unquote(name)),
+                compass: Some(CompassPoint::from_ast(*compass)),
+            },
+            ast::Port::ID(name, None) => {
+                let name = unquote(name);
+                if let Some(compass) = CompassPoint::from_slice(&name) {
+                    return EdgePort {
+                        name: None,
+                        compass: Some(compass),
+                    };
+                }
+                match name
+                    .rsplit_once(':')
+                    .and_then(|(port, compass)| Some((port, CompassPoint::from_slice(compass)?)))
+                {
+                    Some((port, compass)) => EdgePort {
+                        name: Some(port.to_string()),
+                        compass: Some(compass),
+                    },
+                    None => EdgePort {
+                        name: Some(name),
+                        compass: None,
+                    },
+                }
+            }
+            ast::Port::Compass(compass) => EdgePort {
+                name: None,
+                compass: Some(CompassPoint::from_ast(*compass)),
+            },
+        }
+    }
+}
@@ -1,80 +1,149 @@
 //! Draw svg Edges between nodes in a graph
-use crate::perfect_arrows::{get_box_to_box_arrow, ArrowOptions, Pos2, Vec2};
+use crate::attributes::Attributes;
+use crate::edge_dir::EdgeDir;
+use crate::edge_port::EdgePort;
+use crate::edge_router::{
+    route_curved, route_orthogonal, route_self_loop, route_straight, Obstacles,
+};
+use crate::edge_routing::EdgeRouting;
+use crate::layout_observer::{LayoutObserver, NodeBox, NodeLayout, NodeScope};
+use crate::perfect_arrows::{ArrowOptions, ArrowPath, Pos2, Shape, Vec2};
+use dioxus::logger::tracing;
 use dioxus::prelude::*;
-use quadtree_rs::area::{Area, AreaBuilder};
-use quadtree_rs::point::Point;
-use quadtree_rs::Quadtree;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::f64::consts::PI;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 // /// edge-arena const string slice
 // pub const EDGE_ARENA_ID: &str = "edge-arena";
 
-#[derive(Clone, Debug, PartialEq)]
-struct Rect {
-    top: f64,
-    right: f64,
-    bottom: f64,
-    left: f64,
-    width: f64,
-    height: f64,
+/// Extra bow added per parallel edge so edges between the same pair of nodes fan out
+const PARALLEL_EDGE_BOW: f64 = 0.2;
+
+/// Geometry of the edges in an arena, by edge id, worked out by the arena's passes
+#[derive(Clone, Copy)]
+struct ArenaGeometry {
+    paths: Signal<HashMap<String, EdgeSvgData>>,
+}
+
+// The edges as of the latest render, for the arena's next geometry pass
+#[derive(Default)]
+struct GeometryPass {
+    edges: Vec<EdgeData>,
+    routing: EdgeRouting,
+    /// The edges changed since the last pass, so every one is worked out again
+    stale: bool,
+    /// Edges that could not be drawn and were already reported, so each is logged once
+    warned: HashSet<String>,
 }
 
+// Source of unique ids for arena elements
+static NEXT_ARENA: AtomicUsize = AtomicUsize::new(0);
+
 /// Owned Edge data
 #[derive(Clone, Debug, PartialEq)]
 pub struct EdgeData {
     pub id: String,
     pub source: String,
     pub target: String,
+    /// Port and compass point on the source node (`a:out:e -> b`)
+    pub source_port: Option<EdgePort>,
+    /// Port and compass point on the target node
+    pub target_port: Option<EdgePort>,
     pub label: Option<String>,
+    /// Which ends get an arrowhead, from the `dir` attribute or the graph kind
+    pub dir: EdgeDir,
+    /// All attributes from the edge attribute list, including `label`
+    pub attributes: Attributes,
 }
 
 /// SVG data for rendering edges
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 struct EdgeSvgData {
-    path: String,
+    path: ArrowPath,
     arrow_transform: String,
+    start_arrow_transform: String,
     label_x: f64,
     label_y: f64,
 }
 
-/// Represents a straight line segment
-#[derive(Clone, Copy)]
-struct Segment {
-    start: (f32, f32),
-    end: (f32, f32),
-}
-
-/// Axis-aligned bounding box
-#[derive(Clone, Copy)]
-struct BoundingBox {
-    x: f32,
-    y: f32,
-    width: f32,
-    height: f32,
-}
-
-impl BoundingBox {
-    fn contains_point(&self, px: f32, py: f32) -> bool {
-        px >= self.x && px <= self.x + self.width && py >= self.y && py <= self.y + self.height
-    }
-
-    pub fn area(&self) -> Area<u32> {
-        AreaBuilder::default()
-            .anchor(Point {
-                x: self.x as u32,
-                y: self.y as u32,
-            })
-            .dimensions((self.width as u32, self.height as u32))
-            .build()
-            .unwrap()
+/// Arena that shows the Edges overlaid on the children.
+/// `routing` picks how the edges are drawn, curved by default.
+/// Nodes are measured once a frame for the whole arena, and only the edges
+/// touching nodes that moved are worked out again.
+///
+/// `namespace` is the arena's DOM id and the prefix of its nodes' DOM ids (see
+/// [`node_dom_id`]), read on the first render. Without one the arena numbers itself,
+/// which gives different ids on every mount and may not match between server
+/// rendering and hydration.
+#[component]
+pub fn EdgeArena(
+    edges: Vec<EdgeData>,
+    node_ids: Vec<String>,
+    #[props(default)] routing: EdgeRouting,
+    namespace: Option<String>,
+    children: Element,
+) -> Element {
+    let arena_id = use_hook(|| {
+        namespace
+            .unwrap_or_else(|| format!("edge-arena-{}", NEXT_ARENA.fetch_add(1, Ordering::Relaxed)))
+    });
+    // Node ids are namespaced per arena, so graphs sharing a page don't cross-wire
+    let scope = use_context_provider(|| NodeScope::new(format!("{arena_id}-")));
+    let paths = use_signal(HashMap::<String, EdgeSvgData>::new);
+    use_context_provider(|| ArenaGeometry { paths });
+
+    // New edges or routing make the next pass work out every edge
+    let pass = use_hook(|| Rc::new(RefCell::new(GeometryPass::default())));
+    let edges_changed = {
+        let mut pass = pass.borrow_mut();
+        let changed = pass.edges != edges || pass.routing != routing;
+        if changed {
+            pass.edges = edges.clone();
+            pass.routing = routing;
+            pass.stale = true;
+        }
+        changed
+    };
+
+    // Watch the nodes once the arena is in the page; edges follow them from then on
+    let mut observer = use_signal(|| None::<LayoutObserver>);
+    let watched_ids = node_ids.clone();
+    let watched_arena = arena_id.clone();
+    let watched_pass = pass.clone();
+    use_effect(move || {
+        let Some(arena) = web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.get_element_by_id(&watched_arena))
+        else {
+            return;
+        };
+        let runtime = Runtime::current().ok();
+        let pass = watched_pass.clone();
+        let watching = LayoutObserver::new(
+            arena,
+            scope.clone(),
+            watched_ids.clone(),
+            move |changed, layouts| {
+                let _guard = runtime.clone().map(RuntimeGuard::new);
+                let layout = ArenaLayout::new(layouts);
+                update_paths(&layout, &mut pass.borrow_mut(), changed, paths);
+            },
+        );
+        observer.set(watching.ok());
+    });
+    if let Some(observer) = observer.peek().as_ref() {
+        observer.set_node_ids(node_ids.clone());
+        if edges_changed {
+            observer.schedule();
+        }
     }
-}
 
-/// Arena that shows the Edges overlaid on the children
-#[component]
-pub fn EdgeArena(edges: Vec<EdgeData>, node_ids: Vec<String>, children: Element) -> Element {
     rsx! {
         div {
+            id: "{arena_id}",
             class: "relative w-full h-full",
             "data-edge-arena": true,
 
@@ -82,11 +151,12 @@ pub fn EdgeArena(edges: Vec<EdgeData>, node_ids: Vec<String>, children: Element)
 
             svg {
                 class: "absolute top-0 left-0 w-full h-full pointer-events-none overflow-visible",
+                "data-edge-layer": true,
                 {edges.iter().map(|edge| {
                     rsx! {
                         EdgeRenderer {
+                            key: "{edge.id}",
                             edge: edge.clone(),
-                            node_ids: node_ids.clone()
                         }
                     }
                 })}
@@ -95,90 +165,83 @@ pub fn EdgeArena(edges: Vec<EdgeData>, node_ids: Vec<String>, children: Element)
     }
 }
 
-// Helper to build arrow segments from start, center, end
-fn build_arrow_segments(start: Pos2, center: Pos2, end: Pos2) -> [Segment; 2] {
-    [
-        Segment {
-            start: (start.x as f32, start.y as f32),
-            end: (center.x as f32, center.y as f32),
-        },
-        Segment {
-            start: (center.x as f32, center.y as f32),
-            end: (end.x as f32, end.y as f32),
-        },
-    ]
-}
+// One geometry pass: work out the edges touching the nodes in `changed`, or every
+// edge when the edges themselves changed, against a single index of the node boxes
+fn update_paths(
+    layout: &ArenaLayout,
+    pass: &mut GeometryPass,
+    changed: &[String],
+    mut paths: Signal<HashMap<String, EdgeSvgData>>,
+) {
+    let stale = std::mem::take(&mut pass.stale);
+    let changed: HashSet<&str> = changed.iter().map(String::as_str).collect();
+    let affected: Vec<(&EdgeData, usize)> = pass
+        .edges
+        .iter()
+        .zip(parallel_indices(&pass.edges))
+        .filter(|(edge, _)| {
+            stale
+                || changed.contains(edge.source.as_str())
+                || changed.contains(edge.target.as_str())
+        })
+        .collect();
+    if affected.is_empty() && !stale {
+        return;
+    }
 
-// Count collisions for an arrow path
-fn arrow_collision_count(
-    quadtree: &Quadtree<u32, BoundingBox>,
-    arrow_segments: &[Segment],
-) -> usize {
-    let mut count = 0;
-    for seg in arrow_segments {
-        let min_x = seg.start.0.min(seg.end.0);
-        let min_y = seg.start.1.min(seg.end.1);
-        let max_x = seg.start.0.max(seg.end.0);
-        let max_y = seg.start.1.max(seg.end.1);
-        let seg_bbox = BoundingBox {
-            x: min_x,
-            y: min_y,
-            width: max_x - min_x,
-            height: max_y - min_y,
-        };
-        for entry in quadtree.query(seg_bbox.area()) {
-            let node_rect = entry.value_ref();
-            if segment_intersects_rect(seg, node_rect) {
-                count += 1;
+    let mut paths = paths.write();
+    if stale {
+        paths.clear();
+    }
+    for (edge, parallel_index) in affected {
+        match generate_arrow_path_safe(edge, parallel_index, pass.routing, layout) {
+            Ok(data) => {
+                paths.insert(edge.id.clone(), data);
+            }
+            Err(err) => {
+                if pass.warned.insert(edge.id.clone()) {
+                    tracing::warn!("Edge {} is not drawn: {err}", edge.id);
+                }
+                paths.remove(&edge.id);
             }
         }
     }
-    count
 }
 
-// Choose the best arrow flip based on collision count
-fn choose_best_arrow_flip(
-    start: Pos2,
-    start_size: Vec2,
-    end: Pos2,
-    end_size: Vec2,
-    quadtree: &Quadtree<u32, BoundingBox>,
-) -> bool {
-    let mut best_flip = false;
-    let mut min_collisions = usize::MAX;
-
-    for &flip in &[false, true] {
-        let options = ArrowOptions::with_flip(flip);
-        let (start_p, center_p, end_p, _, _, _) =
-            get_box_to_box_arrow(start, start_size, end, end_size, options);
-        let segments = build_arrow_segments(start_p, center_p, end_p);
-        let collisions = arrow_collision_count(quadtree, &segments);
-        if collisions < min_collisions {
-            min_collisions = collisions;
-            best_flip = flip;
-        }
-    }
-    best_flip
+// For each edge, how many earlier edges connect the same pair of nodes (in either direction)
+fn parallel_indices(edges: &[EdgeData]) -> Vec<usize> {
+    let mut seen = std::collections::HashMap::<(&str, &str), usize>::new();
+    edges
+        .iter()
+        .map(|edge| {
+            let pair = if edge.source <= edge.target {
+                (edge.source.as_str(), edge.target.as_str())
+            } else {
+                (edge.target.as_str(), edge.source.as_str())
+            };
+            let count = seen.entry(pair).or_default();
+            let index = *count;
+            *count += 1;
+            index
+        })
+        .collect()
 }
 
-/// A simple component wrapper for edge rendering
+/// DOM id to give the element of node `id` rendered inside an [`EdgeArena`],
+/// unique to that arena. Outside an arena the id is returned unchanged.
+pub fn node_dom_id(id: &str) -> String {
+    try_consume_context::<NodeScope>()
+        .map(|scope| scope.dom_id(id))
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// Draws one edge of an [`EdgeArena`] from the geometry the arena worked out for it
 #[component]
-pub fn EdgeRenderer(edge: EdgeData, node_ids: Vec<String>) -> Element {
-    let mut svg_data = use_signal(|| None::<EdgeSvgData>);
-
-    // Calculate the arrow path when the component mounts
-    let edge_clone = edge.clone();
-    spawn(async move {
-        // Small delay to ensure elements are rendered
-        gloo_timers::future::TimeoutFuture::new(100).await;
-
-        generate_arrow_path_safe(&edge_clone, &node_ids)
-            .map(|data| svg_data.set(Some(data)))
-            .unwrap_or_else(|_err| {
-                // tracing::error!("Error calculating edge {}: {}", edge_clone.id, err);
-                svg_data.set(None);
-            });
-    });
+pub fn EdgeRenderer(edge: EdgeData) -> Element {
+    let geometry = try_use_context::<ArenaGeometry>();
+    let svg_data = use_memo(use_reactive!(|edge| {
+        geometry.and_then(|geometry| geometry.paths.read().get(&edge.id).cloned())
+    }));
     let svg_data = svg_data.read();
     // If we don't have SVG data yet, render nothing
     if svg_data.is_none() {
@@ -192,7 +255,7 @@ pub fn EdgeRenderer(edge: EdgeData, node_ids: Vec<String>) -> Element {
         g {
             key: "{edge.id}",
             path {
-                d: "{data.path}",
+                d: data.path.to_svg(),
                 fill: "none",
                 stroke: "#d1d5db",
                 "stroke-width": "4",
@@ -200,12 +263,23 @@ pub fn EdgeRenderer(edge: EdgeData, node_ids: Vec<String>) -> Element {
                 style: "transition: stroke 0.2s ease; pointer-events: stroke;",
                 "stroke-opacity": "0.4"
             }
-            polygon {
-                points: "-8,-6 0,0 -8,6",
-                fill: "#d1d5db",
-                transform: "{data.arrow_transform}",
-                class: "arrow",
-                style: "transition: fill 0.2s ease; pointer-events: stroke;",
+            if edge.dir.has_end_arrow() {
+                polygon {
+                    points: "-8,-6 0,0 -8,6",
+                    fill: "#d1d5db",
+                    transform: "{data.arrow_transform}",
+                    class: "arrow",
+                    style: "transition: fill 0.2s ease; pointer-events: stroke;",
+                }
+            }
+            if edge.dir.has_start_arrow() {
+                polygon {
+                    points: "-8,-6 0,0 -8,6",
+                    fill: "#d1d5db",
+                    transform: "{data.start_arrow_transform}",
+                    class: "arrow",
+                    style: "transition: fill 0.2s ease; pointer-events: stroke;",
+                }
             }
 
             // Render edge label if present
@@ -235,211 +309,305 @@ pub fn EdgeRenderer(edge: EdgeData, node_ids: Vec<String>) -> Element {
     }
 }
 
-fn generate_arrow_path_safe(edge: &EdgeData, node_ids: &[String]) -> Result<EdgeSvgData, String> {
-    let window = web_sys::window().ok_or("No window")?;
-    let document = window.document().ok_or("No document")?;
-
-    // Get node elements
-    let source_el = document
-        .get_element_by_id(&edge.source)
-        .ok_or(format!("Source node not found: {}", edge.source))?;
-
-    let target_el = document
-        .get_element_by_id(&edge.target)
-        .ok_or(format!("Target node not found: {}", edge.target))?;
-
-    // Get the content container
-    let content_el = source_el
-        .closest("[data-edge-arena]")
-        .map_err(|_| format!("Content container not found for edge {}", edge.id))?
-        .ok_or(format!("Content container not found for edge {}", edge.id))?;
-
-    // Get element coordinates
-    let source = get_coords(&source_el);
-    let target = get_coords(&target_el);
-    let content = get_coords(&content_el);
-
-    // Calculate positions relative to the content container
-    // We use the content container as the reference
-    let x_0 = source.left - content.left;
-    let y_0 = source.top - content.top;
-    let x_1 = target.left - content.left;
-    let y_1 = target.top - content.top;
-
-    let w_0 = source.right - source.left;
-    let h_0 = source.bottom - source.top;
-    let w_1 = target.right - target.left;
-    let h_1 = target.bottom - target.top;
-
-    let start = Pos2 { x: x_0, y: y_0 }; // Use top-left
-    let end = Pos2 { x: x_1, y: y_1 }; // Use top-left
-
-    let start_size = Vec2 { x: w_0, y: h_0 };
-    let end_size = Vec2 { x: w_1, y: h_1 };
-
-    // Build quadtree from all node bounding boxes
-    let mut quadtree = Quadtree::<u32, BoundingBox>::new(12); // 12 levels for large graphs
-    for node_id in node_ids.iter() {
-        if let Some(node_el) = document.get_element_by_id(node_id) {
-            let rect = get_coords(&node_el);
-            let bbox = BoundingBox {
-                x: rect.left as f32,
-                y: rect.top as f32,
-                width: rect.width as f32,
-                height: rect.height as f32,
-            };
-            quadtree.insert(bbox.area(), bbox);
+// Node layouts from one measuring pass, relative to the arena, indexed once for all edges.
+// Working out an edge reads nothing else, the DOM was read while measuring.
+struct ArenaLayout<'a> {
+    layouts: &'a HashMap<String, NodeLayout>,
+    obstacles: Obstacles,
+}
+
+impl<'a> ArenaLayout<'a> {
+    fn new(layouts: &'a HashMap<String, NodeLayout>) -> Self {
+        let mut obstacles = Obstacles::new();
+        for (id, layout) in layouts {
+            let (pos, size) = box_corners(&layout.bounds);
+            obstacles.insert(id.as_str(), pos, size);
         }
+        ArenaLayout { layouts, obstacles }
     }
-    let use_flip = choose_best_arrow_flip(start, start_size, end, end_size, &quadtree);
-    let options = ArrowOptions::with_flip(use_flip);
-
-    let (
-        Pos2 { x: sx, y: sy },
-        Pos2 { x: cx, y: cy },
-        Pos2 { x: ex, y: ey },
-        angle_end,
-        _angle_start,
-        _angle_center,
-    ) = get_box_to_box_arrow(start, start_size, end, end_size, options);
-
-    let path = format!(
-        "M{sx},{sy} Q{cx},{cy} {ex},{ey}",
-        sx = sx,
-        sy = sy,
-        cx = cx,
-        cy = cy,
-        ex = ex,
-        ey = ey
-    );
-
-    let end_angle_as_degrees = angle_end * (180.0 / PI);
-    let arrow_transform = format!("translate({}, {}) rotate({})", ex, ey, end_angle_as_degrees);
 
-    // Calculate midpoint on the curve (t=0.5 on the quadratic bezier)
-    let t = 0.5;
-    let mt = 1.0 - t;
-    let mid_x = mt * mt * sx + 2.0 * mt * t * cx + t * t * ex;
-    let mid_y = mt * mt * sy + 2.0 * mt * t * cy + t * t * ey;
+    // Layout of node `id`
+    fn node(&self, id: &str) -> Result<&NodeLayout, String> {
+        self.layouts
+            .get(id)
+            .ok_or(format!("Node not measured: {id}"))
+    }
+}
 
-    // Calculate tangent vector at midpoint
-    let dx_mid = 2.0 * (mt * (cx - sx) + t * (ex - cx));
-    let dy_mid = 2.0 * (mt * (cy - sy) + t * (ey - cy));
+// Top left and size of a node box
+fn box_corners(node: &NodeBox) -> (Pos2, Vec2) {
+    (
+        Pos2 {
+            x: node.x,
+            y: node.y,
+        },
+        Vec2 {
+            x: node.width,
+            y: node.height,
+        },
+    )
+}
 
-    // Calculate normal vector (perpendicular to tangent)
-    let len = (dx_mid * dx_mid + dy_mid * dy_mid).sqrt();
-    let nx = -dy_mid / len;
-    let ny = dx_mid / len;
+fn generate_arrow_path_safe(
+    edge: &EdgeData,
+    parallel_index: usize,
+    routing: EdgeRouting,
+    layout: &ArenaLayout,
+) -> Result<EdgeSvgData, String> {
+    // Boxes relative to the arena, of the named port inside the node when there is one
+    let source = layout.node(&edge.source)?;
+    let target = layout.node(&edge.target)?;
+    let (start, start_size) = end_box(source, edge.source_port.as_ref());
+    let (end, end_size) = end_box(target, edge.target_port.as_ref());
+    let obstacles = &layout.obstacles;
+
+    // Parallel edges bow progressively further out so each stays visible
+    let options = ArrowOptions {
+        bow: parallel_index as f64 * PARALLEL_EDGE_BOW,
+        ..Default::default()
+    };
+
+    // End on the requested compass point, or on the drawn outline of shaped nodes
+    // rather than on their bounding box
+    let start_shape = compass_anchor(edge.source_port.as_ref(), start, start_size)
+        .map(|center| Shape::Circle {
+            center,
+            radius: 0.0,
+        })
+        .or_else(|| node_outline(source, edge.source_port.as_ref()))
+        .unwrap_or(Shape::Rect {
+            pos: start,
+            size: start_size,
+            radius: 0.0,
+        });
+    let end_shape = compass_anchor(edge.target_port.as_ref(), end, end_size)
+        .map(|center| Shape::Circle {
+            center,
+            radius: 0.0,
+        })
+        .or_else(|| node_outline(target, edge.target_port.as_ref()))
+        .unwrap_or(Shape::Rect {
+            pos: end,
+            size: end_size,
+            radius: 0.0,
+        });
+
+    // Edges from a node back to the same spot loop out of its side, nested by parallel index
+    let self_loop = edge.source == edge.target && edge.source_port == edge.target_port;
+    let ends = [edge.source.as_str(), edge.target.as_str()];
+    let path = if self_loop {
+        // Loops go round the whole outline, whatever compass point they name
+        let shape = node_outline(source, edge.source_port.as_ref()).unwrap_or(Shape::Rect {
+            pos: start,
+            size: start_size,
+            radius: 0.0,
+        });
+        route_self_loop(&shape, obstacles, &edge.source, parallel_index, routing)
+    } else {
+        match routing {
+            EdgeRouting::Curved => {
+                route_curved(&start_shape, &end_shape, &options, obstacles, ends)
+            }
+            EdgeRouting::Straight => route_straight(&start_shape, &end_shape, &options),
+            EdgeRouting::Orthogonal => {
+                route_orthogonal(&start_shape, &end_shape, obstacles, ends, parallel_index)
+            }
+        }
+    };
+    let source_center = Pos2 {
+        x: start.x + start_size.x / 2.0,
+        y: start.y + start_size.y / 2.0,
+    };
+    let start = path.start;
+    let end = path.end();
+
+    let end_angle_as_degrees = path.angle_at(1.0).to_degrees();
+    let arrow_transform = format!(
+        "translate({}, {}) rotate({})",
+        end.x, end.y, end_angle_as_degrees
+    );
 
-    // Determine which side is the "outside" of the curve
-    // We compare the control point position relative to the straight line between start and end
-    let center_x = (sx + ex) / 2.0;
-    let center_y = (sy + ey) / 2.0;
-    let control_side = (cx - center_x) * (ey - sy) - (cy - center_y) * (ex - sx);
+    // The start arrowhead points back against the direction of travel
+    let start_angle_as_degrees = (path.angle_at(0.0) + PI).to_degrees();
+    let start_arrow_transform = format!(
+        "translate({}, {}) rotate({})",
+        start.x, start.y, start_angle_as_degrees
+    );
 
-    // Adjust normal direction based on the curve's concavity
-    // This ensures the label is always on the "outside" of the curve
+    // Label at the middle of the path, offset along the normal
+    let mid = path.point_at(0.5);
     let offset = 20.0; // pixels to offset label from curve
-    let adjusted_nx = if control_side > 0.0 { nx } else { -nx };
-    let adjusted_ny = if control_side > 0.0 { ny } else { -ny };
+    let (label_x, label_y) = if self_loop {
+        // Just beyond the apex of the loop, away from the node
+        let (dx, dy) = (mid.x - source_center.x, mid.y - source_center.y);
+        let len = (dx * dx + dy * dy).sqrt();
+        if len > 0.0 {
+            (mid.x + dx / len * offset, mid.y + dy / len * offset)
+        } else {
+            (mid.x, mid.y)
+        }
+    } else {
+        let tangent = path.tangent_at(0.5);
+        let len = (tangent.x * tangent.x + tangent.y * tangent.y).sqrt();
+        let (nx, ny) = if len > 0.0 {
+            (-tangent.y / len, tangent.x / len)
+        } else {
+            (0.0, -1.0)
+        };
 
-    // Position the label at midpoint + offset in correct normal direction
-    let label_x = mid_x + adjusted_nx * offset;
-    let label_y = mid_y + adjusted_ny * offset;
+        // Keep the label on the side away from where the path bows out of the straight line
+        let bow_x = mid.x - (start.x + end.x) / 2.0;
+        let bow_y = mid.y - (start.y + end.y) / 2.0;
+        let side = if nx * bow_x + ny * bow_y >= 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        (mid.x + nx * side * offset, mid.y + ny * side * offset)
+    };
 
     Ok(EdgeSvgData {
         path,
         arrow_transform,
+        start_arrow_transform,
         label_x,
         label_y,
     })
 }
 
-fn get_coords(el: &web_sys::Element) -> Rect {
-    let rect = el.get_bounding_client_rect();
-
-    // Get window scroll position
-    let window = web_sys::window().unwrap();
-    let page_x_offset = window.page_x_offset().unwrap_or(0.0);
-    let page_y_offset = window.page_y_offset().unwrap_or(0.0);
-
-    // Calculate absolute position (relative to document)
-    Rect {
-        top: rect.top() + page_y_offset,
-        right: rect.right() + page_x_offset,
-        bottom: rect.bottom() + page_y_offset,
-        left: rect.left() + page_x_offset,
+// Box an edge end attaches to: the node's `[data-port=name]` child when the edge
+// names a port, otherwise the node itself
+fn end_box(node: &NodeLayout, port: Option<&EdgePort>) -> (Pos2, Vec2) {
+    let port_box = port
+        .and_then(|port| port.name.as_ref())
+        .and_then(|name| node.ports.get(name));
+    box_corners(port_box.unwrap_or(&node.bounds))
+}
 
-        // Add these for more accurate positioning
-        width: rect.width(),
-        height: rect.height(),
+// Outline, relative to the arena, that a node renderer drew as its `[data-shape]`
+// element. Edges to a named port end on the port's box instead.
+fn node_outline(node: &NodeLayout, port: Option<&EdgePort>) -> Option<Shape> {
+    if port.is_some_and(|port| port.name.is_some()) {
+        return None;
     }
+    let (shape, shape_box) = node.shape.as_ref()?;
+    let (pos, size) = box_corners(shape_box);
+    Some(shape.outline(pos, size))
 }
 
-// Check if a segment intersects a rectangle (simple version)
-fn segment_intersects_rect(seg: &Segment, rect: &BoundingBox) -> bool {
-    // Check if either endpoint is inside the rect
-    if rect.contains_point(seg.start.0, seg.start.1) || rect.contains_point(seg.end.0, seg.end.1) {
-        return true;
-    }
-    // Check for intersection with each edge of the rectangle
-    let edges = [
-        Segment {
-            start: (rect.x, rect.y),
-            end: (rect.x + rect.width, rect.y),
-        }, // top
-        Segment {
-            start: (rect.x, rect.y),
-            end: (rect.x, rect.y + rect.height),
-        }, // left
-        Segment {
-            start: (rect.x + rect.width, rect.y),
-            end: (rect.x + rect.width, rect.y + rect.height),
-        }, // right
-        Segment {
-            start: (rect.x, rect.y + rect.height),
-            end: (rect.x + rect.width, rect.y + rect.height),
-        }, // bottom
-    ];
-    for edge in &edges {
-        if segments_intersect(seg, edge) {
-            return true;
+// Point on the side of the box requested by the port's compass point
+fn compass_anchor(port: Option<&EdgePort>, pos: Pos2, size: Vec2) -> Option<Pos2> {
+    let (fx, fy) = port?.compass?.anchor()?;
+    Some(Pos2 {
+        x: pos.x + size.x * fx,
+        y: pos.y + size.y * fy,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node_shape::NodeShape;
+
+    fn node(x: f64, y: f64) -> NodeLayout {
+        NodeLayout {
+            bounds: NodeBox {
+                x,
+                y,
+                width: 60.0,
+                height: 30.0,
+            },
+            shape: None,
+            ports: HashMap::new(),
         }
     }
-    false
-}
 
-// Helper: Check if two segments intersect
-fn segments_intersect(a: &Segment, b: &Segment) -> bool {
-    fn ccw(p1: (f32, f32), p2: (f32, f32), p3: (f32, f32)) -> bool {
-        (p3.1 - p1.1) * (p2.0 - p1.0) > (p2.1 - p1.1) * (p3.0 - p1.0)
+    fn edge(source: &str, target: &str, source_port: Option<&str>) -> EdgeData {
+        EdgeData {
+            id: format!("{source}-{target}"),
+            source: source.to_string(),
+            target: target.to_string(),
+            source_port: source_port.map(|name| EdgePort {
+                name: Some(name.to_string()),
+                compass: None,
+            }),
+            target_port: None,
+            label: None,
+            dir: EdgeDir::Forward,
+            attributes: Attributes::new(),
+        }
     }
-    let (a1, a2) = (a.start, a.end);
-    let (b1, b2) = (b.start, b.end);
-    (ccw(a1, b1, b2) != ccw(a2, b1, b2)) && (ccw(a1, a2, b1) != ccw(a1, a2, b2))
-}
 
-// Example: Check collisions for an arrow path
-fn arrow_collides(quadtree: &Quadtree<u32, BoundingBox>, arrow_segments: &[Segment]) -> bool {
-    for seg in arrow_segments {
-        // Create bounding box for segment
-        let min_x = seg.start.0.min(seg.end.0);
-        let min_y = seg.start.1.min(seg.end.1);
-        let max_x = seg.start.0.max(seg.end.0);
-        let max_y = seg.start.1.max(seg.end.1);
-        let seg_bbox = BoundingBox {
-            x: min_x,
-            y: min_y,
-            width: max_x - min_x,
-            height: max_y - min_y,
-        };
-        // Query quadtree for nearby nodes
-        for entry in quadtree.query(seg_bbox.area()) {
-            let node_rect = entry.value_ref();
-            if segment_intersects_rect(seg, node_rect) {
-                return true; // Collision detected
+    // Tests run natively, where any call into the DOM panics, so a pass that gets through
+    // hundreds of edges read nothing but the layouts measured once per node
+    #[test]
+    fn edges_are_worked_out_from_the_measured_layouts_alone() {
+        let mut layouts = HashMap::new();
+        let mut edges = Vec::new();
+        for row in 0..12 {
+            for column in 0..12 {
+                let id = format!("n{row}_{column}");
+                layouts.insert(id.clone(), node(column as f64 * 150.0, row as f64 * 100.0));
+                if column > 0 {
+                    edges.push(edge(&format!("n{row}_{}", column - 1), &id, None));
+                }
+                if row > 0 {
+                    edges.push(edge(&format!("n{}_{column}", row - 1), &id, None));
+                }
             }
         }
+        edges.push(edge("n0_0", "n11_11", None));
+        edges.push(edge("n5_5", "n5_5", None));
+
+        // One index of the boxes serves every edge of the pass
+        let layout = ArenaLayout::new(&layouts);
+        assert_eq!(layout.obstacles.iter().count(), layouts.len());
+        for routing in [
+            EdgeRouting::Curved,
+            EdgeRouting::Straight,
+            EdgeRouting::Orthogonal,
+        ] {
+            for (edge, parallel_index) in edges.iter().zip(parallel_indices(&edges)) {
+                assert!(
+                    generate_arrow_path_safe(edge, parallel_index, routing, &layout).is_ok(),
+                    "{}",
+                    edge.id
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn ends_attach_to_the_measured_ports_and_outlines() {
+        let port = NodeBox {
+            x: 40.0,
+            y: 0.0,
+            width: 20.0,
+            height: 30.0,
+        };
+        let mut a = node(0.0, 0.0);
+        a.ports.insert("out".to_string(), port);
+        let mut b = node(300.0, 0.0);
+        b.shape = Some((NodeShape::Ellipse, b.bounds));
+        let layouts = HashMap::from([("a".to_string(), a), ("b".to_string(), b)]);
+        let layout = ArenaLayout::new(&layouts);
+
+        let data = generate_arrow_path_safe(
+            &edge("a", "b", Some("out")),
+            0,
+            EdgeRouting::Straight,
+            &layout,
+        )
+        .unwrap();
+        // Leaves the right side of the port, not of the node
+        assert!((data.path.start.x - 60.0).abs() < 1e-6, "{:?}", data.path);
+        // Arrives on the ellipse, left of its bounding box's corner
+        let end = data.path.end();
+        let (dx, dy) = ((end.x - 330.0) / 30.0, (end.y - 15.0) / 15.0);
+        assert!((dx * dx + dy * dy - 1.0).abs() < 1e-6, "{end:?}");
+
+        let missing =
+            generate_arrow_path_safe(&edge("a", "c", None), 0, EdgeRouting::Straight, &layout);
+        assert_eq!(missing, Err("Node not measured: c".to_string()));
     }
-    false
 }
@@ -0,0 +1,918 @@
+//! Route edges around the nodes they would otherwise cross
+use crate::edge_routing::EdgeRouting;
+use crate::perfect_arrows::{
+    get_shape_to_shape_arrow, ArrowOptions, ArrowPath, PathSegment, Pos2, Shape, Vec2,
+};
+use dioxus::logger::tracing;
+use quadtree_rs::area::{Area, AreaBuilder};
+use quadtree_rs::point::Point;
+use quadtree_rs::Quadtree;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Bow added on top of an edge's own while looking for a curve that misses every node
+const EXTRA_BOWS: [f64; 5] = [0.0, 0.2, 0.4, 0.7, 1.0];
+
+/// Pieces each curve is split into when checking it against node boxes
+const COLLISION_STEPS: usize = 16;
+
+/// Gap kept between a routed path and the boxes it goes around
+const CLEARANCE: f64 = 12.0;
+
+/// Radius of the rounded corners of a routed path
+const CORNER_RADIUS: f64 = 24.0;
+
+/// Radius of the rounded bends of orthogonal edges
+const BEND_RADIUS: f64 = 8.0;
+
+/// Distance between the ports and the runs of parallel orthogonal edges
+const CHANNEL_GAP: f64 = 8.0;
+
+/// How far the innermost self-loop reaches out from its node
+const LOOP_REACH: f64 = 30.0;
+
+/// Gap between nested self-loops on the same node
+const LOOP_GAP: f64 = 14.0;
+
+/// Sides a self-loop can sit on, in order of preference: right, top, bottom, left
+const LOOP_SIDES: [(f64, f64); 4] = [(1.0, 0.0), (0.0, -1.0), (0.0, 1.0), (-1.0, 0.0)];
+
+/// Extra length each turn costs, so routes prefer a few long runs
+const BEND_COST: f64 = 40.0;
+
+/// Routing grids with more points than this are not searched
+const MAX_GRID_POINTS: usize = 100_000;
+
+/// How far past the box around its ends a route first looks for a way, in clearances.
+/// The window doubles until a route is found or it holds every node.
+const WINDOW_MARGIN: f64 = 8.0;
+
+/// Quadtree levels, covering 4096px square. Boxes outside it are checked one by one.
+const QUADTREE_DEPTH: usize = 12;
+
+/// A node box that edges are routed around
+#[derive(Clone, Debug, PartialEq)]
+pub struct Obstacle {
+    pub id: String,
+    /// Top-left corner
+    pub pos: Pos2,
+    pub size: Vec2,
+}
+
+impl Obstacle {
+    /// Whether `p` is strictly inside the box
+    pub fn contains(&self, p: Pos2) -> bool {
+        p.x > self.pos.x
+            && p.x < self.pos.x + self.size.x
+            && p.y > self.pos.y
+            && p.y < self.pos.y + self.size.y
+    }
+
+    /// Whether the box overlaps the box at `pos` with `size`
+    pub fn overlaps(&self, pos: Pos2, size: Vec2) -> bool {
+        self.pos.x < pos.x + size.x
+            && pos.x < self.pos.x + self.size.x
+            && self.pos.y < pos.y + size.y
+            && pos.y < self.pos.y + self.size.y
+    }
+
+    /// Whether the segment from `a` to `b` touches the box, clipped Liang-Barsky style
+    pub fn intersects(&self, a: Pos2, b: Pos2) -> bool {
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let (mut t0, mut t1) = (0.0_f64, 1.0_f64);
+        for (p, q) in [
+            (-dx, a.x - self.pos.x),
+            (dx, self.pos.x + self.size.x - a.x),
+            (-dy, a.y - self.pos.y),
+            (dy, self.pos.y + self.size.y - a.y),
+        ] {
+            if p == 0.0 {
+                // Parallel to this side and outside it
+                if q < 0.0 {
+                    return false;
+                }
+            } else if p < 0.0 {
+                t0 = t0.max(q / p);
+            } else {
+                t1 = t1.min(q / p);
+            }
+        }
+        t0 <= t1
+    }
+}
+
+/// The node boxes of a graph, indexed by a quadtree for collision checks
+pub struct Obstacles {
+    boxes: Vec<Obstacle>,
+    /// Index of each node's box, by id
+    by_id: HashMap<String, usize>,
+    quadtree: Quadtree<u32, usize>,
+    /// Boxes the quadtree can't hold
+    unindexed: Vec<usize>,
+}
+
+impl Default for Obstacles {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Obstacles {
+    pub fn new() -> Self {
+        Obstacles {
+            boxes: Vec::new(),
+            by_id: HashMap::new(),
+            quadtree: Quadtree::new(QUADTREE_DEPTH),
+            unindexed: Vec::new(),
+        }
+    }
+
+    /// Add the box of node `id` with its top-left corner at `pos`
+    pub fn insert(&mut self, id: impl Into<String>, pos: Pos2, size: Vec2) {
+        let index = self.boxes.len();
+        let indexed = quadtree_area(pos, size).and_then(|area| self.quadtree.insert(area, index));
+        if indexed.is_none() {
+            self.unindexed.push(index);
+        }
+        let id = id.into();
+        self.by_id.entry(id.clone()).or_insert(index);
+        self.boxes.push(Obstacle { id, pos, size });
+    }
+
+    /// The box of node `id`
+    pub fn get(&self, id: &str) -> Option<&Obstacle> {
+        self.by_id.get(id).map(|&index| &self.boxes[index])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Obstacle> {
+        self.boxes.iter()
+    }
+
+    // Top-left and bottom-right corners of the box around every node
+    fn extent(&self) -> Option<(Pos2, Pos2)> {
+        self.boxes.iter().fold(None, |extent, o| {
+            let (min, max) = extent.unwrap_or((o.pos, o.pos));
+            Some((
+                Pos2 {
+                    x: min.x.min(o.pos.x),
+                    y: min.y.min(o.pos.y),
+                },
+                Pos2 {
+                    x: max.x.max(o.pos.x + o.size.x),
+                    y: max.y.max(o.pos.y + o.size.y),
+                },
+            ))
+        })
+    }
+
+    // Indices of the boxes that may overlap the box at `pos` with `size`
+    fn near(&self, pos: Pos2, size: Vec2) -> Vec<usize> {
+        let mut found: Vec<usize> = quadtree_area(pos, size)
+            .map(|area| {
+                self.quadtree
+                    .query(area)
+                    .map(|entry| *entry.value_ref())
+                    .collect()
+            })
+            .unwrap_or_default();
+        found.extend(&self.unindexed);
+        found
+    }
+
+    /// Whether `p` is strictly inside any box
+    pub fn contains(&self, p: Pos2) -> bool {
+        self.near(p, Vec2 { x: 0.0, y: 0.0 })
+            .into_iter()
+            .any(|i| self.boxes[i].contains(p))
+    }
+
+    /// Number of boxes, other than the `ignore`d ones, that overlap the box at `pos` with `size`
+    pub fn overlapping(&self, pos: Pos2, size: Vec2, ignore: &[&str]) -> usize {
+        self.near(pos, size)
+            .into_iter()
+            .filter(|&i| {
+                let obstacle = &self.boxes[i];
+                !ignore.contains(&obstacle.id.as_str()) && obstacle.overlaps(pos, size)
+            })
+            .count()
+    }
+
+    /// Whether the segment from `a` to `b` touches any box other than the `ignore`d ones
+    pub fn blocks(&self, a: Pos2, b: Pos2, ignore: &[&str]) -> bool {
+        self.hits(a, b, ignore).next().is_some()
+    }
+
+    // Indices of the boxes, other than the `ignore`d ones, the segment touches
+    fn hits<'a>(
+        &'a self,
+        a: Pos2,
+        b: Pos2,
+        ignore: &'a [&str],
+    ) -> impl Iterator<Item = usize> + 'a {
+        let pos = Pos2 {
+            x: a.x.min(b.x),
+            y: a.y.min(b.y),
+        };
+        let size = Vec2 {
+            x: (a.x - b.x).abs(),
+            y: (a.y - b.y).abs(),
+        };
+        self.near(pos, size).into_iter().filter(move |&i| {
+            let obstacle = &self.boxes[i];
+            !ignore.contains(&obstacle.id.as_str()) && obstacle.intersects(a, b)
+        })
+    }
+
+    /// Number of boxes, other than the `ignore`d ones, that the path crosses
+    pub fn crossings(&self, path: &ArrowPath, ignore: &[&str]) -> usize {
+        let mut crossed: Vec<usize> = path
+            .flatten(COLLISION_STEPS)
+            .windows(2)
+            .flat_map(|pair| self.hits(pair[0], pair[1], ignore).collect::<Vec<_>>())
+            .collect();
+        crossed.sort_unstable();
+        crossed.dedup();
+        crossed.len()
+    }
+}
+
+// Quadtree area covering the box. Areas can't be empty or start left of or above the origin.
+fn quadtree_area(pos: Pos2, size: Vec2) -> Option<Area<u32>> {
+    AreaBuilder::default()
+        .anchor(Point {
+            x: pos.x.max(0.0) as u32,
+            y: pos.y.max(0.0) as u32,
+        })
+        .dimensions(((size.x.ceil() as u32).max(1), (size.y.ceil() as u32).max(1)))
+        .build()
+        .ok()
+}
+
+/// Curved path for an edge between the nodes `ends` (source and target ids) drawn as `start`
+/// and `end`. Curves with more bow, flipped either way, are tried until one misses every other
+/// node. When they all cross something the edge is routed around the nodes instead.
+pub fn route_curved(
+    start: &Shape,
+    end: &Shape,
+    options: &ArrowOptions,
+    obstacles: &Obstacles,
+    ends: [&str; 2],
+) -> ArrowPath {
+    let mut best: Option<(usize, ArrowPath)> = None;
+    for extra in EXTRA_BOWS {
+        for flip in [options.flip, !options.flip] {
+            let candidate = ArrowOptions {
+                bow: options.bow + extra,
+                flip,
+                ..options.clone()
+            };
+            let path = ArrowPath::from(&get_shape_to_shape_arrow(start, end, candidate));
+            let crossings = obstacles.crossings(&path, &ends);
+            if crossings == 0 {
+                return path;
+            }
+            if best.as_ref().is_none_or(|(fewest, _)| crossings < *fewest) {
+                best = Some((crossings, path));
+            }
+        }
+    }
+    let Some((_, curve)) = best else {
+        return ArrowPath::from(&get_shape_to_shape_arrow(start, end, options.clone()));
+    };
+
+    // Leave each node straight out from its outline, then find a way between the two
+    let from = curve.start;
+    let to = curve.end();
+    let leave = escape_point(from, start, obstacles.get(ends[0]));
+    let arrive = escape_point(to, end, obstacles.get(ends[1]));
+    let Some(route) = grid_route(leave, arrive, obstacles, CLEARANCE) else {
+        tracing::debug!(
+            "No clear route from {} to {}, the edge crosses other nodes",
+            ends[0],
+            ends[1]
+        );
+        return curve;
+    };
+    let mut points = vec![from];
+    points.extend(route);
+    points.push(to);
+    ArrowPath::rounded_polyline(&shortcut(&points, obstacles, ends), CORNER_RADIUS)
+}
+
+/// Straight line between the outlines of `start` and `end`. Edges with a bow, like the
+/// later ones of several parallel edges, are curved by it so they stay apart.
+pub fn route_straight(start: &Shape, end: &Shape, options: &ArrowOptions) -> ArrowPath {
+    if options.bow != 0.0 {
+        return ArrowPath::from(&get_shape_to_shape_arrow(start, end, options.clone()));
+    }
+    ArrowPath {
+        start: start.boundary_point(end.center()),
+        segments: vec![PathSegment::Line {
+            to: end.boundary_point(start.center()),
+        }],
+    }
+}
+
+/// Right-angled path for an edge between the nodes `ends` (source and target ids) drawn as
+/// `start` and `end`, going around every other node with rounded bends. The edge leaves and
+/// enters on the sides facing the other node. `channel` is the edge's position among parallel
+/// edges: each runs alongside the first one, so they don't draw over each other.
+pub fn route_orthogonal(
+    start: &Shape,
+    end: &Shape,
+    obstacles: &Obstacles,
+    ends: [&str; 2],
+    channel: usize,
+) -> ArrowPath {
+    let (c0, c1) = (start.center(), end.center());
+    let (dx, dy) = (c1.x - c0.x, c1.y - c0.y);
+    let direction = if dx.abs() >= dy.abs() {
+        (if dx < 0.0 { -1.0 } else { 1.0 }, 0.0)
+    } else {
+        (0.0, if dy < 0.0 { -1.0 } else { 1.0 })
+    };
+    // Route the middle channel with room to spare for this one, then shift it across
+    let offset = channel_offset(channel);
+    let clearance = CLEARANCE + offset.abs();
+    let (from, leave) = side_port(start, obstacles.get(ends[0]), direction, clearance);
+    let (to, arrive) = side_port(
+        end,
+        obstacles.get(ends[1]),
+        (-direction.0, -direction.1),
+        clearance,
+    );
+
+    let route = grid_route(leave, arrive, obstacles, clearance).unwrap_or_else(|| {
+        tracing::debug!(
+            "No clear route from {} to {}, the edge crosses other nodes",
+            ends[0],
+            ends[1]
+        );
+        // Nothing clear, so take the direct Z shape through the middle
+        if direction.1 == 0.0 {
+            let x = (leave.x + arrive.x) / 2.0;
+            vec![
+                leave,
+                Pos2 { x, y: leave.y },
+                Pos2 { x, y: arrive.y },
+                arrive,
+            ]
+        } else {
+            let y = (leave.y + arrive.y) / 2.0;
+            vec![
+                leave,
+                Pos2 { x: leave.x, y },
+                Pos2 { x: arrive.x, y },
+                arrive,
+            ]
+        }
+    });
+    let mut points = vec![from];
+    points.extend(route);
+    points.push(to);
+    let points = shift_sideways(&without_straight_joints(&points), offset, start, end);
+    ArrowPath::rounded_polyline(&points, BEND_RADIUS)
+}
+
+/// Loop from node `id`, drawn as `shape`, back to itself. The loop sits on the side with
+/// the fewest other nodes next to it, preferring the right. `nesting` is the loop's position
+/// among the node's self-loops: each one reaches further out around the ones before it.
+/// Orthogonal routing draws square loops with rounded corners.
+pub fn route_self_loop(
+    shape: &Shape,
+    obstacles: &Obstacles,
+    id: &str,
+    nesting: usize,
+    routing: EdgeRouting,
+) -> ArrowPath {
+    let (pos, size) = shape.bounds();
+    let center = shape.center();
+    let reach = LOOP_REACH + nesting as f64 * LOOP_GAP;
+
+    // The space the innermost loop takes up beside each side of the node's box
+    let (node_pos, node_size) = obstacles
+        .get(id)
+        .map(|node| (node.pos, node.size))
+        .unwrap_or((pos, size));
+    let beside = |(dx, dy): (f64, f64)| {
+        let depth = LOOP_REACH + CLEARANCE;
+        let x = match dx {
+            dx if dx > 0.0 => node_pos.x + node_size.x,
+            dx if dx < 0.0 => node_pos.x - depth,
+            _ => node_pos.x,
+        };
+        let y = match dy {
+            dy if dy > 0.0 => node_pos.y + node_size.y,
+            dy if dy < 0.0 => node_pos.y - depth,
+            _ => node_pos.y,
+        };
+        let (w, h) = if dx != 0.0 {
+            (depth, node_size.y)
+        } else {
+            (node_size.x, depth)
+        };
+        obstacles.overlapping(Pos2 { x, y }, Vec2 { x: w, y: h }, &[id])
+    };
+    let (dx, dy) = LOOP_SIDES
+        .into_iter()
+        .min_by_key(|&side| beside(side))
+        .unwrap_or(LOOP_SIDES[0]);
+
+    // Feet of the loop on the outline, either side of the middle of the chosen side
+    let (ax, ay) = (-dy, dx);
+    let side_len = if dx != 0.0 { size.y } else { size.x };
+    let spread = (reach / 3.0).min(side_len * 0.45).max(0.0);
+    let on_side = |along: f64| {
+        let point = Pos2 {
+            x: center.x + dx * size.x / 2.0 + ax * along,
+            y: center.y + dy * size.y / 2.0 + ay * along,
+        };
+        let outward = Pos2 {
+            x: point.x + dx,
+            y: point.y + dy,
+        };
+        onto_outline(shape, point, outward)
+    };
+    let from = on_side(-spread);
+    let to = on_side(spread);
+
+    if routing == EdgeRouting::Orthogonal {
+        let out = |p: Pos2| Pos2 {
+            x: p.x + dx * reach,
+            y: p.y + dy * reach,
+        };
+        return ArrowPath::rounded_polyline(&[from, out(from), out(to), to], BEND_RADIUS);
+    }
+    // A cubic's middle is 3/4 of the way to its control points, so this puts the apex at `reach`
+    let height = reach * 4.0 / 3.0;
+    ArrowPath {
+        start: from,
+        segments: vec![PathSegment::Cubic {
+            control1: Pos2 {
+                x: from.x + dx * height - ax * spread,
+                y: from.y + dy * height - ay * spread,
+            },
+            control2: Pos2 {
+                x: to.x + dx * height + ax * spread,
+                y: to.y + dy * height + ay * spread,
+            },
+            to,
+        }],
+    }
+}
+
+// Sideways shift of the `channel`-th parallel edge: 0, +gap, -gap, +2 gap, -2 gap...
+fn channel_offset(channel: usize) -> f64 {
+    let step = channel.div_ceil(2) as f64 * CHANNEL_GAP;
+    if channel % 2 == 1 {
+        step
+    } else {
+        -step
+    }
+}
+
+// Port in the middle of the side of the shape facing `direction`, a unit vector along one
+// axis. Returns the port on the outline and the point `clearance` beyond the node's box
+// that the route leaves from.
+fn side_port(
+    shape: &Shape,
+    node: Option<&Obstacle>,
+    direction: (f64, f64),
+    clearance: f64,
+) -> (Pos2, Pos2) {
+    let (pos, size) = shape.bounds();
+    let center = shape.center();
+    let (dx, dy) = direction;
+    let side = |min: f64, len: f64, d: f64| if d > 0.0 { min + len } else { min };
+    let port = shape.boundary_point(if dy == 0.0 {
+        Pos2 {
+            x: side(pos.x, size.x, dx),
+            y: center.y,
+        }
+    } else {
+        Pos2 {
+            x: center.x,
+            y: side(pos.y, size.y, dy),
+        }
+    });
+
+    let (pos, size) = node
+        .map(|node| (node.pos, node.size))
+        .unwrap_or((pos, size));
+    let clear = if dy == 0.0 {
+        Pos2 {
+            x: side(pos.x, size.x, dx) + dx * clearance,
+            y: port.y,
+        }
+    } else {
+        Pos2 {
+            x: port.x,
+            y: side(pos.y, size.y, dy) + dy * clearance,
+        }
+    };
+    (port, clear)
+}
+
+// Move an orthogonal polyline `offset` to the left of its direction of travel, keeping
+// its ends on the outlines of `start` and `end`
+fn shift_sideways(points: &[Pos2], offset: f64, start: &Shape, end: &Shape) -> Vec<Pos2> {
+    let normals: Vec<(f64, f64)> = points
+        .windows(2)
+        .map(|pair| {
+            let (dx, dy) = (pair[1].x - pair[0].x, pair[1].y - pair[0].y);
+            let len = (dx * dx + dy * dy).sqrt();
+            if len > 0.0 {
+                (dy / len, -dx / len)
+            } else {
+                (0.0, 0.0)
+            }
+        })
+        .collect();
+    let (Some(&first), Some(&last)) = (normals.first(), normals.last()) else {
+        return points.to_vec();
+    };
+    // Stay well inside the sides the path leaves and enters through
+    let across = |shape: &Shape, (nx, _): (f64, f64)| {
+        let (_, size) = shape.bounds();
+        if nx != 0.0 {
+            size.x * 0.4
+        } else {
+            size.y * 0.4
+        }
+    };
+    let limit = across(start, first).min(across(end, last)).max(0.0);
+    let offset = offset.clamp(-limit, limit);
+    if offset == 0.0 {
+        return points.to_vec();
+    }
+
+    let mut shifted: Vec<Pos2> = points
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            // Corners move along both runs they join
+            let before = i.checked_sub(1).map(|j| normals[j]).unwrap_or((0.0, 0.0));
+            let after = normals.get(i).copied().unwrap_or((0.0, 0.0));
+            Pos2 {
+                x: p.x + (before.0 + after.0) * offset,
+                y: p.y + (before.1 + after.1) * offset,
+            }
+        })
+        .collect();
+    let n = shifted.len();
+    shifted[0] = onto_outline(start, shifted[0], shifted[1]);
+    shifted[n - 1] = onto_outline(end, shifted[n - 1], shifted[n - 2]);
+    shifted
+}
+
+// Slide `point`, at or outside the shape, away from `away` until it reaches the outline.
+// Points whose line misses the shape stay where they are.
+fn onto_outline(shape: &Shape, point: Pos2, away: Pos2) -> Pos2 {
+    let (dx, dy) = (point.x - away.x, point.y - away.y);
+    let len = (dx * dx + dy * dy).sqrt();
+    if !len.is_finite() || len == 0.0 {
+        return point;
+    }
+    let (_, size) = shape.bounds();
+    let center = shape.center();
+    let inside = |t: f64| {
+        let q = Pos2 {
+            x: point.x + dx / len * t,
+            y: point.y + dy / len * t,
+        };
+        let edge = shape.boundary_point(q);
+        let from_center = |p: Pos2| (p.x - center.x).hypot(p.y - center.y);
+        from_center(q) < from_center(edge)
+    };
+    let (mut outside, mut within) = (0.0, size.x.max(size.y));
+    if !inside(within / 2.0) {
+        return point;
+    }
+    within /= 2.0;
+    for _ in 0..40 {
+        let mid = (outside + within) / 2.0;
+        if inside(mid) {
+            within = mid;
+        } else {
+            outside = mid;
+        }
+    }
+    Pos2 {
+        x: point.x + dx / len * outside,
+        y: point.y + dy / len * outside,
+    }
+}
+
+// Drop repeated points and points in the middle of a straight run, including runs that
+// double back on themselves
+fn without_straight_joints(points: &[Pos2]) -> Vec<Pos2> {
+    let mut kept: Vec<Pos2> = Vec::with_capacity(points.len());
+    for &p in points {
+        while let [.., a, b] = kept[..] {
+            let cross = (b.x - a.x) * (p.y - b.y) - (b.y - a.y) * (p.x - b.x);
+            let scale = (b.x - a.x).hypot(b.y - a.y) * (p.x - b.x).hypot(p.y - b.y);
+            if cross.abs() > 1e-9 * scale.max(1.0) {
+                break;
+            }
+            kept.pop();
+        }
+        if kept.last() != Some(&p) {
+            kept.push(p);
+        }
+    }
+    kept
+}
+
+// Point just clear of the node's box, straight out from the shape's center through `point`
+fn escape_point(point: Pos2, shape: &Shape, node: Option<&Obstacle>) -> Pos2 {
+    let center = shape.center();
+    let (dx, dy) = (point.x - center.x, point.y - center.y);
+    let len = (dx * dx + dy * dy).sqrt();
+    if !len.is_finite() || len == 0.0 {
+        return point;
+    }
+    let (dx, dy) = (dx / len, dy / len);
+    let (pos, size) = node
+        .map(|node| (node.pos, node.size))
+        .unwrap_or_else(|| shape.bounds());
+
+    // Distance along the ray to the side of the box it leaves through
+    let exit = |p: f64, d: f64, min: f64, max: f64| match d {
+        d if d > 0.0 => (max - p) / d,
+        d if d < 0.0 => (min - p) / d,
+        _ => f64::INFINITY,
+    };
+    let t = exit(point.x, dx, pos.x, pos.x + size.x)
+        .min(exit(point.y, dy, pos.y, pos.y + size.y))
+        .max(0.0);
+    Pos2 {
+        x: point.x + dx * (t + CLEARANCE),
+        y: point.y + dy * (t + CLEARANCE),
+    }
+}
+
+// Drop corners of a polyline between the nodes `ends` wherever a straight line can skip them
+// without touching a box. The first and last points sit on the end nodes' outlines, so those
+// two boxes are checked from a step away, which still keeps a shortcut out of its own nodes.
+fn shortcut(points: &[Pos2], obstacles: &Obstacles, ends: [&str; 2]) -> Vec<Pos2> {
+    let Some(&first) = points.first() else {
+        return Vec::new();
+    };
+    let last = points.len() - 1;
+    let clear = |i: usize, j: usize| {
+        let (mut a, mut b) = (points[i], points[j]);
+        if obstacles.blocks(a, b, &ends) {
+            return false;
+        }
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let step = (CLEARANCE / dx.hypot(dy)).min(0.5);
+        if i == 0 {
+            a = Pos2 {
+                x: a.x + dx * step,
+                y: a.y + dy * step,
+            };
+        }
+        if j == last {
+            b = Pos2 {
+                x: b.x - dx * step,
+                y: b.y - dy * step,
+            };
+        }
+        ends.iter()
+            .filter_map(|id| obstacles.get(id))
+            .all(|obstacle| !obstacle.intersects(a, b))
+    };
+    let mut kept = vec![first];
+    let mut i = 0;
+    while i < last {
+        let next = (i + 2..points.len())
+            .rev()
+            .find(|&j| clear(i, j))
+            .unwrap_or(i + 1);
+        kept.push(points[next]);
+        i = next;
+    }
+    kept
+}
+
+// Entry in the search queue, ordered so the heap pops the cheapest first
+struct Candidate {
+    estimate: f64,
+    state: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+// Grid coordinates: the ends plus lines `clearance` away from each side of every box.
+// Lines that nearly meet an end are dropped so the route lines up with the ends exactly.
+fn grid_lines(ends: [f64; 2], sides: impl Iterator<Item = (f64, f64)>, clearance: f64) -> Vec<f64> {
+    let mut lines: Vec<f64> = sides
+        .flat_map(|(min, max)| [min - clearance, max + clearance])
+        .filter(|line| line.is_finite())
+        .collect();
+    lines.sort_by(f64::total_cmp);
+    lines.dedup_by(|a, b| (*a - *b).abs() < 0.5);
+    lines.retain(|line| ends.iter().all(|end| (line - end).abs() >= 0.5));
+    lines.extend(ends.into_iter().filter(|end| end.is_finite()));
+    lines.sort_by(f64::total_cmp);
+    lines.dedup();
+    lines
+}
+
+/// Shortest path with few turns from `from` to `to` made of horizontal and vertical runs
+/// that keep `clearance` from every box, found with A* over a sparse grid through the
+/// gaps between the boxes. `None` when the ends can't be joined.
+///
+/// The grid only holds the boxes near the ends, so its size follows the neighbourhood of
+/// the edge rather than the whole graph. When no route fits, the window grows.
+fn grid_route(from: Pos2, to: Pos2, obstacles: &Obstacles, clearance: f64) -> Option<Vec<Pos2>> {
+    let (min, max) = obstacles.extent().unwrap_or((from, to));
+    let mut margin =
+        (to.x - from.x).abs().max((to.y - from.y).abs()) / 2.0 + WINDOW_MARGIN * clearance;
+    loop {
+        let window_min = Pos2 {
+            x: from.x.min(to.x) - margin,
+            y: from.y.min(to.y) - margin,
+        };
+        let window_max = Pos2 {
+            x: from.x.max(to.x) + margin,
+            y: from.y.max(to.y) + margin,
+        };
+        let holds_every_box = window_min.x < min.x - clearance
+            && window_min.y < min.y - clearance
+            && window_max.x > max.x + clearance
+            && window_max.y > max.y + clearance;
+        match window_route(from, to, obstacles, clearance, (window_min, window_max)) {
+            Ok(Some(route)) => return Some(route),
+            Ok(None) if !holds_every_box => margin *= 2.0,
+            Ok(None) => return None,
+            Err(points) => {
+                tracing::debug!("Routing grid of {points} points is too large to search");
+                return None;
+            }
+        }
+    }
+}
+
+// A* over the grid through the boxes inside `window` (top-left and bottom-right corners),
+// bordered by the window's edges. `Err` holds the size of a grid too large to search.
+fn window_route(
+    from: Pos2,
+    to: Pos2,
+    obstacles: &Obstacles,
+    clearance: f64,
+    (window_min, window_max): (Pos2, Pos2),
+) -> Result<Option<Vec<Pos2>>, usize> {
+    let near: Vec<&Obstacle> = obstacles
+        .near(
+            window_min,
+            Vec2 {
+                x: window_max.x - window_min.x,
+                y: window_max.y - window_min.y,
+            },
+        )
+        .into_iter()
+        .map(|i| &obstacles.boxes[i])
+        .collect();
+    let within = |mut lines: Vec<f64>, min: f64, max: f64| {
+        lines.retain(|&line| line > min && line < max);
+        lines.insert(0, min);
+        lines.push(max);
+        lines
+    };
+    let xs = within(
+        grid_lines(
+            [from.x, to.x],
+            near.iter().map(|o| (o.pos.x, o.pos.x + o.size.x)),
+            clearance,
+        ),
+        window_min.x,
+        window_max.x,
+    );
+    let ys = within(
+        grid_lines(
+            [from.y, to.y],
+            near.iter().map(|o| (o.pos.y, o.pos.y + o.size.y)),
+            clearance,
+        ),
+        window_min.y,
+        window_max.y,
+    );
+    if xs.len() * ys.len() > MAX_GRID_POINTS {
+        return Err(xs.len() * ys.len());
+    }
+    Ok(search_grid(from, to, obstacles, &xs, &ys))
+}
+
+// A* from `from` to `to` along the grid lines `xs` and `ys`, charging for turns
+fn search_grid(
+    from: Pos2,
+    to: Pos2,
+    obstacles: &Obstacles,
+    xs: &[f64],
+    ys: &[f64],
+) -> Option<Vec<Pos2>> {
+    let nearest = |lines: &[f64], v: f64| {
+        (0..lines.len()).min_by(|&a, &b| (lines[a] - v).abs().total_cmp(&(lines[b] - v).abs()))
+    };
+    let start = (nearest(xs, from.x)?, nearest(ys, from.y)?);
+    let goal = (nearest(xs, to.x)?, nearest(ys, to.y)?);
+    let point = |(ix, iy): (usize, usize)| Pos2 {
+        x: xs[ix],
+        y: ys[iy],
+    };
+    let open =
+        |cell: (usize, usize)| cell == start || cell == goal || !obstacles.contains(point(cell));
+
+    // States are a grid point and the direction it was entered from, so turns can be charged
+    const DIRECTIONS: [(isize, isize); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+    let state_of = |(ix, iy): (usize, usize), dir: usize| ((iy * xs.len() + ix) * 5) + dir;
+    let cell_of = |state: usize| {
+        let cell = state / 5;
+        ((cell % xs.len(), cell / xs.len()), state % 5)
+    };
+    let heuristic = |cell: (usize, usize)| {
+        let p = point(cell);
+        let q = point(goal);
+        (p.x - q.x).abs() + (p.y - q.y).abs()
+    };
+
+    let states = xs.len() * ys.len() * 5;
+    let mut cost = vec![f64::INFINITY; states];
+    let mut came_from = vec![usize::MAX; states];
+    let mut queue = BinaryHeap::new();
+    // Direction 4 marks the start, where the first run is free to go any way
+    let first = state_of(start, 4);
+    cost[first] = 0.0;
+    queue.push(Candidate {
+        estimate: heuristic(start),
+        state: first,
+    });
+
+    while let Some(Candidate { state, estimate }) = queue.pop() {
+        let (cell, dir) = cell_of(state);
+        if estimate > cost[state] + heuristic(cell) {
+            continue;
+        }
+        if cell == goal {
+            let mut path = vec![to];
+            let mut at = came_from[state];
+            while at != usize::MAX && at != first {
+                path.push(point(cell_of(at).0));
+                at = came_from[at];
+            }
+            path.push(from);
+            path.reverse();
+            path.dedup();
+            return Some(path);
+        }
+        for (next_dir, (dx, dy)) in DIRECTIONS.into_iter().enumerate() {
+            let (Some(ix), Some(iy)) = (
+                cell.0.checked_add_signed(dx).filter(|&ix| ix < xs.len()),
+                cell.1.checked_add_signed(dy).filter(|&iy| iy < ys.len()),
+            ) else {
+                continue;
+            };
+            let next = (ix, iy);
+            if !open(next) || obstacles.blocks(point(cell), point(next), &[]) {
+                continue;
+            }
+            let a = point(cell);
+            let b = point(next);
+            let turn = if dir != 4 && dir != next_dir {
+                BEND_COST
+            } else {
+                0.0
+            };
+            let next_cost = cost[state] + (a.x - b.x).abs() + (a.y - b.y).abs() + turn;
+            let next_state = state_of(next, next_dir);
+            if next_cost < cost[next_state] {
+                cost[next_state] = next_cost;
+                came_from[next_state] = state;
+                queue.push(Candidate {
+                    estimate: next_cost + heuristic(next),
+                    state: next_state,
+                });
+            }
+        }
+    }
+    None
+}
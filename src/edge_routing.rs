@@ -0,0 +1,36 @@
+use crate::attributes::Attributes;
+
+/// EdgeRouting is how edges are drawn between their nodes.
+/// It corresponds to the 'splines' graph attribute in DOT language.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum EdgeRouting {
+    #[default]
+    Curved, // Curves that bow around nodes in the way (default)
+    Straight,   // Straight lines from node to node
+    Orthogonal, // Horizontal and vertical runs with rounded corners
+}
+
+impl EdgeRouting {
+    // Parse from string slice, including the Graphviz aliases
+    pub fn from_slice(s: &str) -> Self {
+        match s.trim_matches('"') {
+            "ortho" => EdgeRouting::Orthogonal,
+            "line" | "false" | "no" | "polyline" => EdgeRouting::Straight,
+            _ => EdgeRouting::Curved, // Default to curves
+        }
+    }
+
+    /// The routing selected by a graph's `splines` attribute
+    pub fn from_attributes(attributes: &Attributes) -> Self {
+        Self::from_slice(attributes.get("splines").unwrap_or_default())
+    }
+
+    // Convert to string for DOT output
+    pub const fn to_str(self) -> &'static str {
+        match self {
+            EdgeRouting::Curved => "spline",
+            EdgeRouting::Straight => "line",
+            EdgeRouting::Orthogonal => "ortho",
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Typed errors for DOT input, with the location of the problem in the source.
+use crate::attributes::unquote;
+use crate::preprocess::Token;
+use dot_parser::ast::PestError;
+use pest::error::InputLocation;
+use std::fmt;
+
+/// Location of a problem in the DOT source.
+/// `start` and `end` are byte offsets, `line` and `column` are 1-based.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    /// Build a span from byte offsets into `source`
+    pub fn from_offsets(source: &str, start: usize, end: usize) -> Self {
+        let start = start.min(source.len());
+        let end = end.clamp(start, source.len());
+        let before = &source[..start];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        Span {
+            start,
+            end,
+            line,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+
+    /// The full source line containing the start of the span
+    pub fn source_line<'a>(&self, source: &'a str) -> &'a str {
+        source
+            .lines()
+            .nth(self.line.saturating_sub(1))
+            .unwrap_or("")
+    }
+
+    /// Number of characters to underline on the span's first line (at least one)
+    pub fn underline_len(&self, source: &str) -> usize {
+        let end = self.end.min(source.len());
+        let text = source.get(self.start..end).unwrap_or("");
+        text.lines().next().unwrap_or("").chars().count().max(1)
+    }
+}
+
+/// Errors produced while turning DOT source into [`GraphData`](crate::graph_data::GraphData)
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    /// The input is not valid DOT
+    Syntax { message: String, span: Span },
+    /// An edge references a node that was never declared and implicit nodes are disabled
+    UnknownNode { node: String, span: Span },
+    /// A subgraph id declared again under a different parent graph, where it would
+    /// name a second, unrelated subgraph. Re-opening a subgraph in the same parent
+    /// adds to the first declaration instead.
+    DuplicateSubgraph { id: String, span: Span },
+    /// Valid DOT that dioxus-plumb can't handle yet
+    Unsupported { feature: String, span: Span },
+}
+
+/// What an error found while walking the AST refers to. The AST has no positions,
+/// so the span is looked up afterwards in the tokens of the source.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Anchor {
+    /// The subgraph opened by the n-th `{` after the graph's own, counting from zero
+    Subgraph(usize),
+    /// The first place `id` is used as a node, in a node or edge statement
+    Node(String),
+}
+
+impl ParseError {
+    /// Where in the source the error occurred
+    pub fn span(&self) -> &Span {
+        match self {
+            ParseError::Syntax { span, .. }
+            | ParseError::UnknownNode { span, .. }
+            | ParseError::DuplicateSubgraph { span, .. }
+            | ParseError::Unsupported { span, .. } => span,
+        }
+    }
+
+    // Convert a pest error, `original_offset` maps offsets in the parsed
+    // (preprocessed) text back to `source`.
+    pub(crate) fn from_pest(
+        source: &str,
+        err: &PestError,
+        original_offset: impl Fn(usize) -> usize,
+    ) -> Self {
+        let (start, end) = match err.location {
+            InputLocation::Pos(pos) => (pos, pos + 1),
+            InputLocation::Span(span) => span,
+        };
+        ParseError::Syntax {
+            message: err.variant.message().to_string(),
+            span: Span::from_offsets(source, original_offset(start), original_offset(end)),
+        }
+    }
+
+    // Errors raised while walking the AST don't know their position yet, find what
+    // `anchor` refers to in the `tokens` of `source`. When it can't be found the error
+    // points at the start of the source.
+    pub(crate) fn locate(mut self, source: &str, tokens: &[Token], anchor: &Anchor) -> Self {
+        let found = match anchor {
+            Anchor::Subgraph(index) => subgraph_token(tokens, *index),
+            Anchor::Node(id) => node_tokens(tokens).find(|token| unquote(token.text) == *id),
+        };
+        let span = found
+            .map(|token| Span::from_offsets(source, token.start, token.end))
+            .unwrap_or_else(|| Span::from_offsets(source, 0, 0));
+        match &mut self {
+            ParseError::Syntax { span: s, .. }
+            | ParseError::UnknownNode { span: s, .. }
+            | ParseError::DuplicateSubgraph { span: s, .. }
+            | ParseError::Unsupported { span: s, .. } => *s = span,
+        }
+        self
+    }
+
+    // dot-parser stops reading after the first graph's closing brace. Whatever follows
+    // it, other than comments, is either another graph or not DOT at all.
+    pub(crate) fn after_graph(source: &str, tokens: &[Token]) -> Option<Self> {
+        let mut depth = 0;
+        let body_end = tokens.iter().position(|token| {
+            match token.text {
+                "{" => depth += 1,
+                "}" => depth -= 1,
+                _ => return false,
+            }
+            depth == 0
+        })?;
+        let next = tokens.get(body_end + 1)?;
+        let span = Span::from_offsets(source, next.start, next.end);
+        Some(match next.text {
+            "strict" | "graph" | "digraph" => ParseError::Unsupported {
+                feature: "more than one graph in the source".to_string(),
+                span,
+            },
+            _ => ParseError::Syntax {
+                message: "expected end of input".to_string(),
+                span,
+            },
+        })
+    }
+}
+
+// The id of the subgraph opened by the n-th `{` after the graph's own, or its `subgraph`
+// keyword or brace when it has no id
+fn subgraph_token<'a>(tokens: &[Token<'a>], index: usize) -> Option<Token<'a>> {
+    let brace = tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, token)| token.text == "{")
+        .nth(index + 1)?
+        .0;
+    let before = |n: usize| brace.checked_sub(n).map(|i| tokens[i]);
+    match (before(2), before(1)) {
+        (Some(keyword), Some(id)) if keyword.text == "subgraph" && id.is_id() => Some(id),
+        (_, Some(keyword)) if keyword.text == "subgraph" => Some(keyword),
+        _ => Some(tokens[brace]),
+    }
+}
+
+// Ids used as nodes, in document order. Attribute lists, `ID=ID` statements, ports and
+// graph or subgraph ids are skipped.
+fn node_tokens<'a, 'b>(tokens: &'b [Token<'a>]) -> impl Iterator<Item = Token<'a>> + 'b {
+    let mut in_list = false;
+    tokens.iter().enumerate().filter_map(move |(i, token)| {
+        match token.text {
+            "[" => in_list = true,
+            "]" => in_list = false,
+            _ => {}
+        }
+        let before = i.checked_sub(1).map(|i| tokens[i].text);
+        let after = tokens.get(i + 1).map(|token| token.text);
+        let is_node = !in_list
+            && token.is_id()
+            && !matches!(before, Some("=" | ":" | "subgraph" | "graph" | "digraph"))
+            && after != Some("=");
+        is_node.then_some(*token)
+    })
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Span { line, column, .. } = self.span();
+        match self {
+            ParseError::Syntax { message, .. } => {
+                write!(f, "Syntax error at {line}:{column}: {message}")
+            }
+            ParseError::UnknownNode { node, .. } => {
+                write!(f, "Unknown node \"{node}\" in edge at {line}:{column}")
+            }
+            ParseError::DuplicateSubgraph { id, .. } => {
+                write!(f, "Duplicate subgraph id \"{id}\" at {line}:{column}")
+            }
+            ParseError::Unsupported { feature, .. } => {
+                write!(f, "Unsupported feature at {line}:{column}: {feature}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
@@ -1,6 +1,6 @@
 //! Dashboard Layout
 use dioxus::prelude::*;
-use dioxus_plumb::{edge_renderer::EdgeArena, graph_data::parse_graph};
+use dioxus_plumb::{edge_renderer::EdgeArena, graph_data::GraphData};
 
 // Move the Basic component here as it's specific to this example
 #[component]
@@ -106,29 +106,34 @@ pub fn Dashboard() -> Element {
     let unlock_script = "Unlock Script";
     let your_node = "Your Node";
 
-    let edge_arena_dot_edges = format!(
-        r#"
-        digraph G {{
-            label="Dashboard";
-            node [shape=box, style=filled, fillcolor="\#f0f0f0"];
-            edge [fontname="Arial", fontsize=10];
-
-            "{wasm_cid}" -> "{first_lock_script}" [label="Identifies Content of"];
-            "{vlad_sig}" -> "{wasm_cid}" [label="of"];
-            "{vlad}" -> "{mutable_value}" [label="Maps to"];
-            "{first_lock_script}" -> "{foot}" [label="Secures"];
-            "{mutable_value}" -> "{head}" [label="References"];
-            "{vlad_pubkey}" -> "{vlad_sig}" [label="Verifies"];
-            "{head_prev}" -> "{foot}";
-            "{first_lock}" -> "{first_lock_script}" [label="References"];
-            "{seqno_0}" -> "{seqno_1}" [label="then"];
-            // unlock script to head enables changes 
-            "{unlock_script}" -> "{head}" [label="Unlocks"];
-        }}
-        "#,
-    );
-
-    let graph_data = parse_graph(&edge_arena_dot_edges).unwrap_or_default();
+    let graph_data = GraphData::builder()
+        .label("Dashboard")
+        .node_default("shape", "box")
+        .node_default("style", "filled")
+        .node_default("fillcolor", "#f0f0f0")
+        .edge_default("fontname", "Arial")
+        .edge_default("fontsize", "10")
+        .edge(wasm_cid, first_lock_script)
+        .label("Identifies Content of")
+        .edge(vlad_sig, wasm_cid)
+        .label("of")
+        .edge(vlad, mutable_value)
+        .label("Maps to")
+        .edge(first_lock_script, foot)
+        .label("Secures")
+        .edge(mutable_value, head)
+        .label("References")
+        .edge(vlad_pubkey, vlad_sig)
+        .label("Verifies")
+        .edge(head_prev, foot)
+        .edge(first_lock, first_lock_script)
+        .label("References")
+        .edge(seqno_0, seqno_1)
+        .label("then")
+        // unlock script to head enables changes
+        .edge(unlock_script, head)
+        .label("Unlocks")
+        .build();
 
     rsx! {
         div {
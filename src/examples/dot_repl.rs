@@ -2,9 +2,10 @@
 //! Edit the existing DOT code or upload a .dot file to visualize different graphs.
 use dioxus::{logger::tracing, prelude::*};
 use dioxus_plumb::{
-    dot_renderer::DotGraph,
+    dot_renderer::{DotGraph, ParseErrorView},
     edge_renderer::EdgeArena,
     edge_renderer::EdgeData,
+    error::ParseError,
     graph_data::{parse_graph, GraphData},
     node_renderer::InteractiveNodeRenderer,
 };
@@ -26,6 +27,8 @@ pub fn DotRepl() -> Element {
 
     // State for error messages and parsed data
     let mut error = use_signal(|| Option::<String>::None);
+    // Structured parse error, used to underline the offending DOT source
+    let mut parse_error = use_signal(|| Option::<ParseError>::None);
     let mut graph_data = use_signal(|| Option::<GraphData>::None);
     let mut edges = use_signal(Vec::<EdgeData>::new);
     let mut node_ids = use_signal(HashSet::<String>::new);
@@ -34,9 +37,8 @@ pub fn DotRepl() -> Element {
     let mut parse_dot = move || {
         match parse_graph(&dot_input.read()) {
             Ok(graph) => {
-                // Extract all node IDs from the graph
-                let mut nodes = HashSet::new();
-                extract_node_ids(&graph, &mut nodes);
+                // Extract all node IDs from the graph, at any depth
+                let nodes: HashSet<String> = graph.all_nodes().map(|n| n.id.clone()).collect();
 
                 // Get the edges
                 let extracted_edges = graph.edges.clone();
@@ -46,9 +48,11 @@ pub fn DotRepl() -> Element {
                 edges.set(extracted_edges);
                 graph_data.set(Some(graph));
                 error.set(None);
+                parse_error.set(None);
             }
             Err(err) => {
-                error.set(Some(err));
+                error.set(Some(err.to_string()));
+                parse_error.set(Some(err));
                 graph_data.set(None);
                 edges.set(Vec::new());
                 node_ids.set(HashSet::new());
@@ -56,25 +60,6 @@ pub fn DotRepl() -> Element {
         }
     };
 
-    // Helper function to extract node IDs from the graph recursively
-    fn extract_node_ids(graph: &GraphData, nodes: &mut HashSet<String>) {
-        // Add nodes from this level
-        for node in &graph.nodes {
-            nodes.insert(node.id.clone());
-        }
-
-        // Add edge nodes (may not be explicitly defined as nodes)
-        for edge in &graph.edges {
-            nodes.insert(edge.source.clone());
-            nodes.insert(edge.target.clone());
-        }
-
-        // Recursively process subgraphs
-        for subgraph in &graph.subgraphs {
-            extract_node_ids(subgraph, nodes);
-        }
-    }
-
     // Parse the initial DOT input
     use_effect(move || {
         parse_dot();
@@ -90,6 +75,8 @@ pub fn DotRepl() -> Element {
 
         match parse_graph(&dot_input.read()) {
             Ok(graph) => {
+                parse_error.set(None);
+
                 // Check for potential issues with complex graphs
                 if graph.edges.len() > 50 {
                     // Just a warning, still allow rendering
@@ -121,6 +108,7 @@ pub fn DotRepl() -> Element {
             }
             Err(err) => {
                 error.set(Some(format!("Error parsing DOT: {}", err)));
+                parse_error.set(Some(err));
             }
         }
     };
@@ -208,8 +196,13 @@ pub fn DotRepl() -> Element {
                         "Render Graph"
                     }
 
-                    // Error display
-                    if let Some(err_msg) = error.read().as_ref() {
+                    // Error display, parse errors show the offending line
+                    if let Some(parse_err) = parse_error.read().as_ref() {
+                        div {
+                            class: "mt-4",
+                            ParseErrorView { error: parse_err.clone(), source: dot_input.read().clone() }
+                        }
+                    } else if let Some(err_msg) = error.read().as_ref() {
                         div {
                             class: "mt-4 p-3 bg-red-100 border border-red-400 text-red-700 rounded",
                             "{err_msg}"
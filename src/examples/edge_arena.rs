@@ -1,12 +1,16 @@
 use dioxus::prelude::*;
-use dioxus_plumb::{edge_renderer::EdgeArena, graph_data::parse_graph};
+use dioxus_plumb::{
+    edge_renderer::{node_dom_id, EdgeArena},
+    graph_data::GraphData,
+};
 
 // Move the Basic component here as it's specific to this example
 #[component]
 fn Basic(id: String, children: Element) -> Element {
     rsx! {
         div {
-            id,
+            // Unique to the arena, so other graphs on the page can reuse the node id
+            id: node_dom_id(&id),
             class: "p-4 bg-slate-300/60 rounded-lg",
             h2 { class: "text-xl font-semibold", "Source A" }
             p { "This is the content of Source {id}." }
@@ -22,19 +26,13 @@ pub fn EdgeArenaExample() -> Element {
     let node_b = "NodeB";
     let nodes = [node_a, node_b];
 
-    // edges can be derived from the DOT graph or defined manually
-    let edge_arena_dot_edges = format!(
-        r#"
-        digraph G {{
-            label="Edge Arena Edges";
-            "{node_a}" -> "{node_b}" [label="Edge from {node_a} to {node_b}"];
-        }}
-        "#,
-        node_a = node_a,
-        node_b = node_b
-    );
-
-    let graph_data = parse_graph(&edge_arena_dot_edges).unwrap_or_default();
+    // edges can be derived from the DOT graph or built in code
+    let graph_data = GraphData::builder()
+        .label("Edge Arena Edges")
+        .edge(node_a, node_b)
+        .label(format!("Edge from {node_a} to {node_b}"))
+        .build();
+    let edge_arena_dot_edges = graph_data.to_dot();
 
     rsx! {
         div {
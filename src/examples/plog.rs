@@ -140,7 +140,7 @@ fn Graph(graph: GraphData) -> Element {
 
                 // Render nodes
                 {graph.nodes.iter().map(|node| {
-                    let node_type = PlogNodeType::from_str(node.label.as_deref().unwrap_or("")).unwrap_or(PlogNodeType::Cas);
+                    let node_type = PlogNodeType::from_str(node.attributes.get("nodetype").unwrap_or("")).unwrap_or(PlogNodeType::Cas);
                     rsx! {
                         PlogNodeRenderer {
                             id: node.id.clone(),
@@ -0,0 +1,263 @@
+//! Fluent builder for GraphData, for graphs built in code rather than DOT strings.
+//!
+//! ```ignore
+//! let graph = GraphData::builder()
+//!     .label("Dashboard")
+//!     .node_default("shape", "box")
+//!     .node("a").label("A")
+//!     .subgraph("cluster_x", |sg| sg.label("X").node("b"))
+//!     .edge("a", "b").label("uses")
+//!     .build();
+//! ```
+use dot_parser::ast::{self, either::Either};
+
+use crate::{
+    attributes::quote, edge_routing::EdgeRouting, graph_data::GraphData, graph_kind::GraphKind,
+    rankdir::RankDir,
+};
+
+/// Builds a [`GraphData`] statement by statement.
+///
+/// The statements go through the same code path as parsed DOT, so node ids get
+/// the same subgraph path prefixes, `node`/`edge` defaults apply to the statements
+/// after them, and nodes that only appear in edges are created implicitly.
+#[derive(Clone, Debug, Default)]
+pub struct GraphBuilder {
+    kind: GraphKind,
+    strict: bool,
+    statements: Vec<Statement>,
+    /// Index of the node or edge statement that `attr`/`label` apply to
+    target: Option<usize>,
+}
+
+// One DOT statement, with ids and values stored as they would be written in DOT
+#[derive(Clone, Debug)]
+enum Statement {
+    Graph(String, String),
+    NodeDefaults(Vec<(String, String)>),
+    EdgeDefaults(Vec<(String, String)>),
+    Node {
+        id: String,
+        attributes: Vec<(String, String)>,
+    },
+    Edge {
+        source: String,
+        target: String,
+        attributes: Vec<(String, String)>,
+    },
+    Subgraph {
+        id: String,
+        body: GraphBuilder,
+    },
+}
+
+impl GraphData {
+    /// Start building a graph in code, see [`GraphBuilder`]
+    pub fn builder() -> GraphBuilder {
+        GraphBuilder::new()
+    }
+}
+
+impl GraphBuilder {
+    /// Create a builder for an empty `digraph`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether this is a `digraph` or an undirected `graph`
+    pub fn kind(mut self, kind: GraphKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Mark the graph `strict`, merging repeated edges
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Add a node. Following `label`/`attr` calls apply to it.
+    pub fn node(mut self, id: impl AsRef<str>) -> Self {
+        self.push(
+            Statement::Node {
+                id: quote(id.as_ref()),
+                attributes: Vec::new(),
+            },
+            true,
+        );
+        self
+    }
+
+    /// Add an edge between two node ids as written in DOT (without subgraph prefixes).
+    /// Following `label`/`attr` calls apply to it.
+    pub fn edge(mut self, source: impl AsRef<str>, target: impl AsRef<str>) -> Self {
+        self.push(
+            Statement::Edge {
+                source: quote(source.as_ref()),
+                target: quote(target.as_ref()),
+                attributes: Vec::new(),
+            },
+            true,
+        );
+        self
+    }
+
+    /// Add a subgraph, built by `build` from an empty builder.
+    /// Ids starting with `cluster` make it a cluster, as in DOT.
+    pub fn subgraph(
+        mut self,
+        id: impl AsRef<str>,
+        build: impl FnOnce(GraphBuilder) -> GraphBuilder,
+    ) -> Self {
+        let body = build(GraphBuilder {
+            kind: self.kind,
+            strict: self.strict,
+            ..Default::default()
+        });
+        self.push(
+            Statement::Subgraph {
+                id: quote(id.as_ref()),
+                body,
+            },
+            false,
+        );
+        self
+    }
+
+    /// Set the label of the last node or edge, or of the graph when there is none
+    pub fn label(self, label: impl AsRef<str>) -> Self {
+        self.attr("label", label)
+    }
+
+    /// Set an HTML-like label (the text between the outer `<` `>`)
+    pub fn html_label(self, html: impl AsRef<str>) -> Self {
+        let value = format!("<{}>", html.as_ref());
+        self.raw_attr("label", value)
+    }
+
+    /// Set an attribute on the last node or edge added since the last subgraph,
+    /// or on the graph itself when there is none
+    pub fn attr(self, key: impl AsRef<str>, value: impl AsRef<str>) -> Self {
+        let value = quote(value.as_ref());
+        self.raw_attr(key, value)
+    }
+
+    /// Set a graph attribute. Subgraphs added after it inherit it, like `key=value` in DOT.
+    pub fn graph_attr(mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> Self {
+        self.statements.push(Statement::Graph(
+            key.as_ref().to_string(),
+            quote(value.as_ref()),
+        ));
+        self
+    }
+
+    /// Set the graph's layout direction
+    pub fn rankdir(self, direction: RankDir) -> Self {
+        self.graph_attr("rankdir", direction.to_str())
+    }
+
+    /// Set how the graph's edges are routed
+    pub fn splines(self, routing: EdgeRouting) -> Self {
+        self.graph_attr("splines", routing.to_str())
+    }
+
+    /// Default attribute for nodes added after this call, like `node [key=value]`
+    pub fn node_default(mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> Self {
+        let attribute = (key.as_ref().to_string(), quote(value.as_ref()));
+        self.statements
+            .push(Statement::NodeDefaults(vec![attribute]));
+        self
+    }
+
+    /// Default attribute for edges added after this call, like `edge [key=value]`
+    pub fn edge_default(mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> Self {
+        let attribute = (key.as_ref().to_string(), quote(value.as_ref()));
+        self.statements
+            .push(Statement::EdgeDefaults(vec![attribute]));
+        self
+    }
+
+    /// Build the GraphData
+    pub fn build(self) -> GraphData {
+        let ast_graph = ast::Graph {
+            strict: self.strict,
+            is_digraph: self.kind == GraphKind::Directed,
+            name: None,
+            stmts: self.stmt_list(),
+        };
+        GraphData::from_ast(&ast_graph)
+    }
+
+    fn push(&mut self, statement: Statement, is_target: bool) {
+        self.statements.push(statement);
+        self.target = is_target.then(|| self.statements.len() - 1);
+    }
+
+    // `value` is already in DOT syntax (quoted or `<...>`)
+    fn raw_attr(mut self, key: impl AsRef<str>, value: String) -> Self {
+        let key = key.as_ref().to_string();
+        match self.target.and_then(|i| self.statements.get_mut(i)) {
+            Some(Statement::Node { attributes, .. } | Statement::Edge { attributes, .. }) => {
+                attributes.push((key, value));
+            }
+            _ => self.statements.push(Statement::Graph(key, value)),
+        }
+        self
+    }
+
+    // Borrow the statements as the dot-parser AST that `from_ast` walks
+    fn stmt_list(&self) -> ast::StmtList<(&str, &str)> {
+        let stmts = self
+            .statements
+            .iter()
+            .map(|statement| match statement {
+                Statement::Graph(key, value) => ast::Stmt::IDEq(key.clone(), value.clone()),
+                Statement::NodeDefaults(attributes) => {
+                    ast::Stmt::AttrStmt(ast::AttrStmt::Node(attr_list(attributes)))
+                }
+                Statement::EdgeDefaults(attributes) => {
+                    ast::Stmt::AttrStmt(ast::AttrStmt::Edge(attr_list(attributes)))
+                }
+                Statement::Node { id, attributes } => ast::Stmt::NodeStmt(ast::NodeStmt {
+                    node: node_id(id),
+                    attr: (!attributes.is_empty()).then(|| attr_list(attributes)),
+                }),
+                Statement::Edge {
+                    source,
+                    target,
+                    attributes,
+                } => ast::Stmt::EdgeStmt(ast::EdgeStmt {
+                    from: Either::Left(node_id(source)),
+                    next: ast::EdgeRHS {
+                        to: Either::Left(node_id(target)),
+                        next: None,
+                    },
+                    attr: (!attributes.is_empty()).then(|| attr_list(attributes)),
+                }),
+                Statement::Subgraph { id, body } => ast::Stmt::Subgraph(ast::Subgraph {
+                    id: Some(id.clone()),
+                    stmts: body.stmt_list(),
+                }),
+            })
+            .collect();
+        ast::StmtList { stmts }
+    }
+}
+
+fn node_id(id: &str) -> ast::NodeID {
+    ast::NodeID {
+        id: id.to_string(),
+        port: None,
+    }
+}
+
+fn attr_list(attributes: &[(String, String)]) -> ast::AttrList<(&str, &str)> {
+    ast::AttrList {
+        elems: vec![ast::AList {
+            elems: attributes
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_str()))
+                .collect(),
+        }],
+    }
+}
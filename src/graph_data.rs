@@ -1,8 +1,19 @@
 use dioxus::logger::tracing;
-use dot_parser::{ast, canonical};
+use dot_parser::ast::{self, either::Either};
 use std::collections::{HashMap, HashSet};
 
-use crate::{edge_renderer::EdgeData, rankdir::RankDir};
+use crate::{
+    attributes::{unquote, Attributes},
+    edge_dir::EdgeDir,
+    edge_port::EdgePort,
+    edge_renderer::EdgeData,
+    error::{Anchor, ParseError},
+    graph_kind::GraphKind,
+    html_label::{parse_html_label, HtmlNode},
+    preprocess::preprocess,
+    rankdir::RankDir,
+    record::{is_record_shape, parse_record, RecordField},
+};
 
 /// Type alias for attributes in the AST
 /// This is used to represent key-value pairs in the AST, where both key and value are
@@ -19,6 +30,14 @@ pub struct GraphData {
     pub subgraphs: Vec<GraphData>, // Recursive structure
     pub edges: Vec<EdgeData>,      // Edges within this (sub)graph scope
     pub direction: RankDir,
+    /// All graph attributes from `graph [...]` and `ID=ID` statements
+    pub attributes: Attributes,
+    /// Whether this is a Graphviz cluster (`subgraph cluster_x`) rather than a grouping subgraph
+    pub is_cluster: bool,
+    /// `digraph` or `graph`, subgraphs share the kind of their root graph
+    pub kind: GraphKind,
+    /// Whether the graph was declared `strict` (no multi-edges)
+    pub strict: bool,
 }
 
 /// Owned representation of the node data
@@ -26,28 +45,62 @@ pub struct GraphData {
 pub struct NodeData {
     pub id: String,
     pub label: Option<String>,
+    /// All attributes from the node statement, including `label`
+    pub attributes: Attributes,
+    /// Fields of a `shape=record` or `Mrecord` label
+    pub record: Option<Vec<RecordField>>,
+    /// Element tree of an HTML-like label (`label=<...>`)
+    pub html_label: Option<Vec<HtmlNode>>,
+}
+
+/// Options for turning DOT into GraphData
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseOptions {
+    /// Create nodes for ids that only appear in edges (Graphviz behaviour).
+    /// When false, such edges are reported as [`ParseError::UnknownNode`].
+    pub implicit_nodes: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            implicit_nodes: true,
+        }
+    }
 }
 
 impl GraphData {
+    /// Build GraphData from a parsed DOT AST. This never fails: problems that
+    /// [`parse_graph`] would report are tolerated.
     pub fn from_ast(ast_graph: &ast::Graph<Att>) -> Self {
-        // Extract graph label and ID
-        let label = find_graph_label(&ast_graph.stmts);
+        Self::build(ast_graph, &ParseOptions::default()).0
+    }
+
+    // Build the graph, collecting the problems found along the way with what they refer to
+    fn build(
+        ast_graph: &ast::Graph<Att>,
+        options: &ParseOptions,
+    ) -> (Self, Vec<(ParseError, Anchor)>) {
+        // Extract graph attributes and ID
+        let attributes = graph_attributes(&ast_graph.stmts);
         let id = "G".to_string(); // Default graph ID
 
-        // Create a node ID map to track all node IDs across subgraphs
-        let mut node_id_map = HashMap::new();
-        // Track nodes that have been placed in subgraphs
-        let mut placed_nodes = HashSet::new();
+        // Shared state for the recursive walk
+        let mut state = ParseState::default();
 
         // Parse the graph recursively
         let mut graph = GraphData {
             id,
-            label,
-            style: None,
+            label: attributes.get("label").map(String::from),
+            style: attributes.get("style").map(String::from),
             nodes: Vec::new(),
             subgraphs: Vec::new(),
             edges: Vec::new(),
-            direction: find_graph_direction(&ast_graph.stmts),
+            direction: RankDir::from_slice(attributes.get("rankdir").unwrap_or_default()),
+            attributes,
+            is_cluster: false,
+            kind: GraphKind::from_is_digraph(ast_graph.is_digraph),
+            strict: ast_graph.strict,
         };
 
         // Parse statements to build the graph structure
@@ -55,142 +108,212 @@ impl GraphData {
             &ast_graph.stmts,
             &mut graph,
             "",
-            &mut node_id_map,
-            &mut placed_nodes,
+            Defaults::default(),
+            &mut state,
         );
 
-        // Create canonical representation for edges
-        let canonical_graph = canonical::Graph::from(ast_graph.clone());
+        // Nodes declared more than once end up in one place with all their attributes
+        apply_node_attributes(&mut graph, &state);
 
-        // Process edges from canonical representation
+        // Strict graphs merge repeated edges into one
+        if graph.strict {
+            state.edges = merge_multi_edges(state.edges, graph.kind);
+        }
+
+        // Resolve the collected edges against the final node id map
         // All edges will be stored only at the top level
-        let edge_data: Vec<EdgeData> = canonical_graph
+        let mut used_edge_ids = HashSet::new();
+        graph.edges = state
             .edges
-            .set
             .iter()
             .map(|edge| {
-                // Get the actual node IDs as they exist in our structure
-                let source_orig = edge.from.trim_matches('"').to_string();
-                let target_orig = edge.to.trim_matches('"').to_string();
-
                 // Look up mapped IDs (these include the full path)
-                let source = node_id_map
-                    .get(&edge.from)
-                    .unwrap_or(&source_orig)
-                    .trim_matches('"')
-                    .to_string();
-                let target = node_id_map
-                    .get(&edge.to)
-                    .unwrap_or(&target_orig)
-                    .trim_matches('"')
-                    .to_string();
+                let source = state
+                    .node_id_map
+                    .get(&edge.source)
+                    .unwrap_or(&edge.source)
+                    .clone();
+                let target = state
+                    .node_id_map
+                    .get(&edge.target)
+                    .unwrap_or(&edge.target)
+                    .clone();
+
+                // Prefer the user's `id` attribute, otherwise use simpler IDs for edges
+                let base_id = edge
+                    .attributes
+                    .get("id")
+                    .map(String::from)
+                    .unwrap_or_else(|| format!("{}-{}", edge.source, edge.target));
 
                 EdgeData {
-                    id: format!("{}-{}", source_orig, target_orig), // Use simpler IDs for edges
+                    id: unique_edge_id(base_id, &mut used_edge_ids),
                     source,
                     target,
-                    label: edge.attr.elems.iter().find_map(|(k, v)| {
-                        if *k == "label" {
-                            Some(v.trim_matches('"').to_string())
-                        } else {
-                            None
-                        }
-                    }),
+                    source_port: edge.source_port.clone(),
+                    target_port: edge.target_port.clone(),
+                    label: edge.attributes.get("label").map(String::from),
+                    dir: edge
+                        .attributes
+                        .get("dir")
+                        .map(|dir| EdgeDir::from_slice(dir, graph.kind))
+                        .unwrap_or(EdgeDir::default_for(graph.kind)),
+                    attributes: edge.attributes.clone(),
                 }
             })
             .collect();
 
-        // Store all edges at the top level
-        graph.edges = edge_data;
-
-        // Add implicit nodes for any node id referenced in edges but not already present
-        let mut existing_node_ids: HashSet<String> =
-            graph.nodes.iter().map(|n| n.id.clone()).collect();
-        for edge in &graph.edges {
-            for node_id in [&edge.source, &edge.target] {
-                if !existing_node_ids.contains(node_id) {
-                    graph.nodes.push(NodeData {
-                        id: node_id.clone(),
-                        label: None, // Will default to id in renderer
-                    });
-                    existing_node_ids.insert(node_id.clone());
+        // Add implicit nodes for any node id referenced in edges but not already present.
+        // They receive the `node [...]` defaults in scope where they were first mentioned.
+        for edge in state.edges {
+            for original_id in [edge.source, edge.target] {
+                let node_id = state
+                    .node_id_map
+                    .get(&original_id)
+                    .cloned()
+                    .unwrap_or(original_id);
+                if graph.find_node(&node_id).is_none() {
+                    if !options.implicit_nodes {
+                        let anchor = Anchor::Node(node_id.clone());
+                        let err = ParseError::UnknownNode {
+                            node: node_id,
+                            span: Default::default(),
+                        };
+                        state.errors.push((err, anchor));
+                        continue;
+                    }
+                    // A missing label defaults to the id in the renderer
+                    graph.nodes.push(node_data(
+                        node_id.clone(),
+                        &node_id,
+                        edge.node_defaults.clone(),
+                    ));
                 }
             }
         }
 
-        graph
+        (graph, state.errors)
     }
 }
 
-// Helper function to check if a node exists in the graph hierarchy
-fn node_exists_in_graph(graph: &GraphData, node_id: &str) -> bool {
-    // Check if node exists directly in this graph
-    if graph.nodes.iter().any(|n| n.id == node_id) {
-        return true;
+/// Default attributes set by `node [...]`, `edge [...]` and `graph [...]` statements.
+/// Each (sub)graph scope works on its own copy, so defaults never leak out of a subgraph.
+#[derive(Clone, Debug, Default)]
+struct Defaults {
+    node: Attributes,
+    edge: Attributes,
+    graph: Attributes,
+}
+
+// Add the attributes of a `node [...]`, `edge [...]` or `graph [...]` statement to the
+// defaults in scope
+fn apply_attr_stmt(defaults: &mut Defaults, attr_stmt: &ast::AttrStmt<Att>) {
+    let (target, attr_list) = match attr_stmt {
+        ast::AttrStmt::Node(list) => (&mut defaults.node, list),
+        ast::AttrStmt::Edge(list) => (&mut defaults.edge, list),
+        ast::AttrStmt::Graph(list) => (&mut defaults.graph, list),
+    };
+    for (key, value) in attr_list.flatten_ref() {
+        target.insert_raw(key, value);
     }
+}
 
-    // Check subgraphs recursively
-    for subgraph in &graph.subgraphs {
-        if node_exists_in_graph(subgraph, node_id) {
-            return true;
-        }
+/// An edge found while walking the statements, resolved once all nodes are known
+struct PendingEdge {
+    source: String,
+    target: String,
+    source_port: Option<EdgePort>,
+    target_port: Option<EdgePort>,
+    attributes: Attributes,
+    /// `node [...]` defaults in scope, used for endpoints that turn out to be implicit
+    node_defaults: Attributes,
+}
+
+/// State shared across the whole recursive walk
+#[derive(Default)]
+struct ParseState {
+    /// Maps original node ids to their path-prefixed ids
+    node_id_map: HashMap<String, String>,
+    /// Path prefix of the (sub)graph each node is placed in, by original id
+    placed_nodes: HashMap<String, String>,
+    /// Attributes of each node merged over all its declarations, by original id
+    node_attributes: HashMap<String, Attributes>,
+    /// Original id of every node placed so far, by prefixed id
+    original_ids: HashMap<String, String>,
+    /// Edges in document order
+    edges: Vec<PendingEdge>,
+    /// Subgraph ids in use, they become DOM ids so must be unique
+    subgraph_ids: HashSet<String>,
+    /// Path prefix of the parent and id of each named subgraph, by declared id
+    declared_subgraphs: HashMap<String, (String, String)>,
+    /// Subgraphs walked so far, in document order
+    subgraph_count: usize,
+    /// Problems found during the walk
+    errors: Vec<(ParseError, Anchor)>,
+}
+
+impl ParseState {
+    // Position of the next subgraph in document order
+    fn next_subgraph(&mut self) -> usize {
+        self.subgraph_count += 1;
+        self.subgraph_count - 1
     }
 
-    false
+    // `id`, or `id_1`, `id_2`... when a subgraph already has it
+    fn unique_subgraph_id(&mut self, id: String) -> String {
+        let mut unique = id.clone();
+        let mut occurrence = 0;
+        while self.subgraph_ids.contains(&unique) {
+            occurrence += 1;
+            unique = format!("{id}_{occurrence}");
+        }
+        self.subgraph_ids.insert(unique.clone());
+        unique
+    }
 }
+
 /// Parse DOT into GraphData
-pub fn parse_graph(dot: &str) -> Result<GraphData, String> {
-    let ast_graph = dot_parser::ast::Graph::<(&str, &str)>::try_from(dot)
-        .map_err(|err| format!("Failed to parse DOT: {err}"))?;
-    Ok(GraphData::from_ast(&ast_graph))
+pub fn parse_graph(dot: &str) -> Result<GraphData, ParseError> {
+    parse_graph_with_options(dot, &ParseOptions::default())
 }
 
-// Find the graph label in statements
-fn find_graph_label(stmts: &ast::StmtList<Att>) -> Option<String> {
-    for stmt in stmts {
-        match stmt {
-            ast::Stmt::AttrStmt(ast::AttrStmt::Graph(attr_list)) => {
-                for element in &attr_list.elems {
-                    for elem in &element.elems {
-                        if elem.0 == "label" {
-                            return Some(elem.1.trim_matches('"').to_string());
-                        }
-                    }
-                }
-            }
-            ast::Stmt::IDEq(key, value) => {
-                if key == "label" {
-                    return Some(value.trim_matches('"').to_string());
-                }
-            }
-            _ => {}
-        }
+/// Parse DOT into GraphData with the given options
+pub fn parse_graph_with_options(
+    dot: &str,
+    options: &ParseOptions,
+) -> Result<GraphData, ParseError> {
+    let source = preprocess(dot);
+    let ast_graph = dot_parser::ast::Graph::<(&str, &str)>::try_from(source.text.as_str())
+        .map_err(|err| ParseError::from_pest(dot, &err, |offset| source.original_offset(offset)))?;
+    let tokens = source.tokens();
+    if let Some(err) = ParseError::after_graph(dot, &tokens) {
+        return Err(err);
+    }
+    let (graph, errors) = GraphData::build(&ast_graph, options);
+    match errors.into_iter().next() {
+        Some((err, anchor)) => Err(err.locate(dot, &tokens, &anchor)),
+        None => Ok(graph),
     }
-    None
 }
 
-// Find the graph direction in statements
-fn find_graph_direction(stmts: &ast::StmtList<Att>) -> RankDir {
+// Collect the graph attributes declared directly in these statements,
+// from both `graph [...]` and `ID=ID` forms. Nested subgraphs are not visited.
+fn graph_attributes(stmts: &ast::StmtList<Att>) -> Attributes {
+    let mut attributes = Attributes::new();
     for stmt in stmts {
         match stmt {
             ast::Stmt::AttrStmt(ast::AttrStmt::Graph(attr_list)) => {
-                for element in &attr_list.elems {
-                    for elem in &element.elems {
-                        if elem.0 == "rankdir" {
-                            return RankDir::from_slice(elem.1);
-                        }
-                    }
+                for (key, value) in attr_list.flatten_ref() {
+                    attributes.insert_raw(key, value);
                 }
             }
             ast::Stmt::IDEq(key, value) => {
-                if key == "rankdir" {
-                    return RankDir::from_slice(value);
-                }
+                attributes.insert_raw(key, value);
             }
             _ => {}
         }
     }
-    RankDir::default()
+    attributes
 }
 
 // Parse statements to build the graph structure
@@ -198,115 +321,411 @@ fn parse_statements(
     stmts: &ast::StmtList<Att>,
     graph: &mut GraphData,
     path_prefix: &str,
-    node_id_map: &mut HashMap<String, String>,
-    placed_nodes: &mut HashSet<String>, // Track placed nodes
+    mut defaults: Defaults,
+    state: &mut ParseState,
 ) {
     for stmt in stmts {
         match stmt {
+            ast::Stmt::AttrStmt(attr_stmt) => {
+                apply_attr_stmt(&mut defaults, attr_stmt);
+            }
+            ast::Stmt::IDEq(key, value) => {
+                defaults.graph.insert_raw(key, value);
+            }
             ast::Stmt::NodeStmt(node_stmt) => {
-                // Extract node info
-                let original_id = node_stmt.node.id.clone();
-
-                // Create node ID with path prefix to ensure uniqueness
-                let node_id = if path_prefix.is_empty() {
-                    original_id.clone()
-                } else {
-                    format!("{path_prefix}-{original_id}")
-                };
-
-                // Map the original ID to our node ID
-                node_id_map.insert(original_id.clone(), node_id.clone());
-
-                // Mark this node as placed
-                placed_nodes.insert(original_id.clone());
-
-                let node_label = node_stmt.attr.as_ref().and_then(|attr| {
-                    attr.clone().flatten().into_iter().find_map(|(key, value)| {
-                        if key == "label" {
-                            Some(value.trim_matches('"').to_string())
-                        } else {
-                            None
-                        }
-                    })
-                });
-
-                graph.nodes.push(NodeData {
-                    id: node_id,
-                    label: node_label,
-                });
+                declare_node(node_stmt, graph, path_prefix, &defaults, state);
+            }
+            ast::Stmt::EdgeStmt(edge_stmt) => {
+                collect_edges(edge_stmt, graph, path_prefix, &defaults, state);
             }
             ast::Stmt::Subgraph(subgraph) => {
-                // Extract subgraph ID
-                let subgraph_id = format!("cluster_{}", graph.subgraphs.len());
-
-                // Create unique path prefix for nodes in this subgraph
-                let new_path_prefix = if path_prefix.is_empty() {
-                    subgraph_id.clone()
-                } else {
-                    format!("{path_prefix}-{subgraph_id}")
-                };
-
-                // Extract subgraph attributes
-                let mut label = None;
-                let mut style = None;
-                let direction = find_graph_direction(&subgraph.stmts);
-                extract_attributes(&subgraph.stmts, &mut label, &mut style);
-
-                // Create the subgraph
-                let mut sub_graph = GraphData {
-                    id: subgraph_id,
-                    label,
-                    style,
-                    nodes: Vec::new(),
-                    subgraphs: Vec::new(),
-                    edges: Vec::new(), // No edges will be stored in subgraphs
-                    direction,
-                };
-
-                // Recursively parse the subgraph's contents
-                parse_statements(
-                    &subgraph.stmts,
-                    &mut sub_graph,
-                    &new_path_prefix,
-                    node_id_map,
-                    placed_nodes,
-                );
-
-                // Add the subgraph to the parent graph
-                graph.subgraphs.push(sub_graph);
+                declare_subgraph(subgraph, graph, path_prefix, &defaults, state);
             }
-            _ => {}
         }
     }
 }
 
-// Helper to extract label and style attributes
-fn extract_attributes(
-    stmts: &ast::StmtList<Att>,
-    label: &mut Option<String>,
-    style: &mut Option<String>,
+// Add a `subgraph` statement to `graph`. Like in Graphviz, a subgraph id declared again
+// in the same parent re-opens that subgraph; declared under another parent it is an error.
+fn declare_subgraph(
+    subgraph: &ast::Subgraph<Att>,
+    graph: &mut GraphData,
+    path_prefix: &str,
+    defaults: &Defaults,
+    state: &mut ParseState,
 ) {
-    for stmt in stmts {
+    let index = state.next_subgraph();
+    // Keep the declared subgraph ID, only anonymous subgraphs get a positional one.
+    // Declared ids that end up the same once made DOM-safe get a suffix.
+    let declared_id = subgraph.id.as_deref().map(unquote);
+    let subgraph_id = match declared_id.as_deref() {
+        Some(declared) => match state.declared_subgraphs.get(declared) {
+            Some((parent, id)) if parent == path_prefix => id.clone(),
+            found => {
+                if found.is_some() {
+                    let err = ParseError::DuplicateSubgraph {
+                        id: declared.to_string(),
+                        span: Default::default(),
+                    };
+                    state.errors.push((err, Anchor::Subgraph(index)));
+                }
+                let id = state.unique_subgraph_id(sanitize_dom_id(declared));
+                state
+                    .declared_subgraphs
+                    .entry(declared.to_string())
+                    .or_insert((path_prefix.to_string(), id.clone()));
+                id
+            }
+        },
+        None => {
+            let id = (graph.subgraphs.len()..)
+                .map(|index| format!("subgraph_{index}"))
+                .find(|id| !state.subgraph_ids.contains(id))
+                .unwrap_or_default();
+            state.subgraph_ids.insert(id.clone());
+            id
+        }
+    };
+
+    // Create unique path prefix for nodes in this subgraph
+    let new_path_prefix = if path_prefix.is_empty() {
+        subgraph_id.clone()
+    } else {
+        format!("{path_prefix}-{subgraph_id}")
+    };
+
+    // Subgraphs inherit the graph defaults declared so far, except the label
+    // which would otherwise be repeated on every nested cluster
+    let mut inherited = defaults.clone();
+    inherited.graph.remove("label");
+
+    // Continue a re-opened subgraph, or create it
+    let reopened = graph
+        .subgraphs
+        .iter()
+        .position(|existing| existing.id == subgraph_id);
+    let mut sub_graph = match reopened {
+        Some(position) => graph.subgraphs.remove(position),
+        None => GraphData {
+            id: subgraph_id,
+            attributes: inherited.graph.clone(),
+            kind: graph.kind,
+            strict: graph.strict,
+            // No edges will be stored in subgraphs
+            ..Default::default()
+        },
+    };
+    sub_graph
+        .attributes
+        .merge(&graph_attributes(&subgraph.stmts));
+    let attributes = &sub_graph.attributes;
+    sub_graph.label = attributes.get("label").map(String::from);
+    sub_graph.style = attributes.get("style").map(String::from);
+    sub_graph.direction = RankDir::from_slice(attributes.get("rankdir").unwrap_or_default());
+    sub_graph.is_cluster = is_cluster(declared_id.as_deref(), attributes);
+
+    // Recursively parse the subgraph's contents
+    parse_statements(
+        &subgraph.stmts,
+        &mut sub_graph,
+        &new_path_prefix,
+        inherited,
+        state,
+    );
+
+    // Add the subgraph to the parent graph, where it was when re-opened
+    match reopened {
+        Some(position) => graph.subgraphs.insert(position, sub_graph),
+        None => graph.subgraphs.push(sub_graph),
+    }
+}
+
+// Declare a node in `graph`. The first declaration creates the node with the `node [...]`
+// defaults in scope, later ones only add their own attributes. Like in Graphviz, a node
+// mentioned again inside a nested subgraph moves into that subgraph.
+fn declare_node(
+    node_stmt: &ast::NodeStmt<Att>,
+    graph: &mut GraphData,
+    path_prefix: &str,
+    defaults: &Defaults,
+    state: &mut ParseState,
+) {
+    let original_id = unquote(&node_stmt.node.id);
+    let mut explicit = Attributes::new();
+    if let Some(attr_list) = &node_stmt.attr {
+        for (key, value) in attr_list.flatten_ref() {
+            explicit.insert_raw(key, value);
+        }
+    }
+
+    let moves = match state.placed_nodes.get(&original_id) {
+        Some(placed) => {
+            state
+                .node_attributes
+                .entry(original_id.clone())
+                .or_default()
+                .merge(&explicit);
+            !path_prefix.is_empty()
+                && (placed.is_empty() || path_prefix.starts_with(&format!("{placed}-")))
+        }
+        None => {
+            // Explicit attributes override the `node [...]` defaults in scope
+            let mut attributes = defaults.node.clone();
+            attributes.merge(&explicit);
+            state
+                .node_attributes
+                .insert(original_id.clone(), attributes);
+            true
+        }
+    };
+    if !moves {
+        return;
+    }
+
+    // Create node ID with path prefix to ensure uniqueness
+    let node_id = if path_prefix.is_empty() {
+        original_id.clone()
+    } else {
+        format!("{path_prefix}-{original_id}")
+    };
+    state
+        .node_id_map
+        .insert(original_id.clone(), node_id.clone());
+    state
+        .placed_nodes
+        .insert(original_id.clone(), path_prefix.to_string());
+    state
+        .original_ids
+        .insert(node_id.clone(), original_id.clone());
+
+    let attributes = state.node_attributes[&original_id].clone();
+    graph
+        .nodes
+        .push(node_data(node_id, &original_id, attributes));
+}
+
+fn node_data(id: String, original_id: &str, attributes: Attributes) -> NodeData {
+    NodeData {
+        id,
+        label: attributes.get("label").map(String::from),
+        record: record_fields(&attributes, original_id),
+        html_label: html_label(&attributes),
+        attributes,
+    }
+}
+
+// Drop the places nodes moved away from, and give every declared node the
+// attributes merged over all its declarations
+fn apply_node_attributes(graph: &mut GraphData, state: &ParseState) {
+    graph.nodes.retain_mut(|node| {
+        let Some(original_id) = state.original_ids.get(&node.id) else {
+            return true;
+        };
+        if state.node_id_map.get(original_id) != Some(&node.id) {
+            return false;
+        }
+        let attributes = state.node_attributes[original_id].clone();
+        *node = node_data(node.id.clone(), original_id, attributes);
+        true
+    });
+    for subgraph in &mut graph.subgraphs {
+        apply_node_attributes(subgraph, state);
+    }
+}
+
+// Merge edges joining the same nodes, later attributes win. In undirected graphs
+// `a -- b` and `b -- a` are the same edge.
+fn merge_multi_edges(edges: Vec<PendingEdge>, kind: GraphKind) -> Vec<PendingEdge> {
+    let mut merged: Vec<PendingEdge> = Vec::new();
+    for edge in edges {
+        let existing = merged.iter_mut().find(|e| {
+            (e.source == edge.source && e.target == edge.target)
+                || (kind == GraphKind::Undirected
+                    && e.source == edge.target
+                    && e.target == edge.source)
+        });
+        match existing {
+            Some(existing) => existing.attributes.merge(&edge.attributes),
+            None => merged.push(edge),
+        }
+    }
+    merged
+}
+
+// Parallel edges share a base id, so later occurrences get an occurrence index
+// appended (`a-b`, `a-b-1`, `a-b-2`). The first occurrence keeps the plain id.
+fn unique_edge_id(base_id: String, used_ids: &mut HashSet<String>) -> String {
+    let mut id = base_id.clone();
+    let mut occurrence = 0;
+    while used_ids.contains(&id) {
+        occurrence += 1;
+        id = format!("{base_id}-{occurrence}");
+    }
+    used_ids.insert(id.clone());
+    id
+}
+
+// Make a subgraph identifier usable as a DOM id and as a node id path segment.
+// Anything but ASCII letters, digits and `_` becomes `_` (`-` is the path separator),
+// and ids that don't start with a letter or `_` get a `subgraph_` prefix.
+fn sanitize_dom_id(id: &str) -> String {
+    let sanitized: String = id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match sanitized.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => sanitized,
+        _ => format!("subgraph_{sanitized}"),
+    }
+}
+
+// Fields of record-shaped nodes. Like in Graphviz, the label defaults to the node id
+// and HTML-like labels are not record labels.
+fn record_fields(attributes: &Attributes, original_id: &str) -> Option<Vec<RecordField>> {
+    (is_record_shape(attributes) && !attributes.is_html("label"))
+        .then(|| parse_record(attributes.get("label").unwrap_or(original_id)))
+}
+
+fn html_label(attributes: &Attributes) -> Option<Vec<HtmlNode>> {
+    attributes
+        .is_html("label")
+        .then(|| parse_html_label(attributes.get("label").unwrap_or_default()))
+}
+
+// Graphviz treats subgraphs named `cluster...` or with `cluster=true` as clusters
+fn is_cluster(declared_id: Option<&str>, attributes: &Attributes) -> bool {
+    declared_id.is_some_and(|id| id.starts_with("cluster"))
+        || attributes.get("cluster") == Some("true")
+}
+
+// Record every edge of an edge statement, expanding chains (`a -> b -> c`)
+// and subgraph endpoints (`a -> { b c }`) into single edges.
+fn collect_edges(
+    edge_stmt: &ast::EdgeStmt<Att>,
+    graph: &mut GraphData,
+    path_prefix: &str,
+    defaults: &Defaults,
+    state: &mut ParseState,
+) {
+    // Explicit attributes override the `edge [...]` defaults in scope
+    let mut attributes = defaults.edge.clone();
+    if let Some(attr_list) = &edge_stmt.attr {
+        for (key, value) in attr_list.flatten_ref() {
+            attributes.insert_raw(key, value);
+        }
+    }
+
+    // Resolve each endpoint of the chain once, then connect consecutive endpoints
+    let mut endpoints = vec![endpoint_ids(
+        &edge_stmt.from,
+        graph,
+        path_prefix,
+        defaults,
+        state,
+    )];
+    let mut rhs = Some(&edge_stmt.next);
+    while let Some(next) = rhs {
+        endpoints.push(endpoint_ids(&next.to, graph, path_prefix, defaults, state));
+        rhs = next.next.as_deref();
+    }
+
+    for pair in endpoints.windows(2) {
+        for (source, source_port) in &pair[0] {
+            for (target, target_port) in &pair[1] {
+                state.edges.push(PendingEdge {
+                    source: source.clone(),
+                    target: target.clone(),
+                    source_port: source_port.clone(),
+                    target_port: target_port.clone(),
+                    attributes: attributes.clone(),
+                    node_defaults: defaults.node.clone(),
+                });
+            }
+        }
+    }
+}
+
+// Node ids of an edge endpoint, with the port for `node:port` endpoints
+fn endpoint_ids(
+    endpoint: &Either<ast::NodeID, ast::Subgraph<Att>>,
+    graph: &mut GraphData,
+    path_prefix: &str,
+    defaults: &Defaults,
+    state: &mut ParseState,
+) -> Vec<(String, Option<EdgePort>)> {
+    match endpoint {
+        Either::Left(node_id) => vec![(
+            unquote(&node_id.id),
+            node_id.port.as_ref().map(EdgePort::from_ast),
+        )],
+        Either::Right(subgraph) => {
+            subgraph_endpoint_ids(subgraph, graph, path_prefix, defaults.clone(), state)
+        }
+    }
+}
+
+// Node ids of a subgraph endpoint (`a -> { b c }`). Its statements are walked like any
+// other, with their own default scope: nodes are declared in the enclosing graph and
+// edges inside are collected as well.
+fn subgraph_endpoint_ids(
+    subgraph: &ast::Subgraph<Att>,
+    graph: &mut GraphData,
+    path_prefix: &str,
+    mut defaults: Defaults,
+    state: &mut ParseState,
+) -> Vec<(String, Option<EdgePort>)> {
+    fn add(ids: &mut Vec<(String, Option<EdgePort>)>, id: &String) {
+        if !ids.iter().any(|(known, _)| known == id) {
+            ids.push((id.clone(), None));
+        }
+    }
+    // Named subgraphs as endpoints would be flattened into their parent, losing the subgraph
+    let index = state.next_subgraph();
+    if subgraph.id.is_some() {
+        let err = ParseError::Unsupported {
+            feature: "subgraphs with an id as edge endpoints".to_string(),
+            span: Default::default(),
+        };
+        state.errors.push((err, Anchor::Subgraph(index)));
+    }
+    let mut ids = Vec::new();
+    for stmt in &subgraph.stmts {
         match stmt {
-            ast::Stmt::IDEq(attr_name, attr_value) => {
-                if attr_name == "label" {
-                    *label = Some(attr_value.trim_matches('"').to_string());
-                } else if attr_name == "style" {
-                    *style = Some(attr_value.trim_matches('"').to_string());
+            ast::Stmt::AttrStmt(attr_stmt) => {
+                apply_attr_stmt(&mut defaults, attr_stmt);
+            }
+            ast::Stmt::IDEq(key, value) => {
+                defaults.graph.insert_raw(key, value);
+            }
+            ast::Stmt::NodeStmt(node_stmt) => {
+                declare_node(node_stmt, graph, path_prefix, &defaults, state);
+                add(&mut ids, &unquote(&node_stmt.node.id));
+            }
+            ast::Stmt::EdgeStmt(edge_stmt) => {
+                let before = state.edges.len();
+                collect_edges(edge_stmt, graph, path_prefix, &defaults, state);
+                let found: Vec<String> = state.edges[before..]
+                    .iter()
+                    .flat_map(|edge| [edge.source.clone(), edge.target.clone()])
+                    .collect();
+                for id in &found {
+                    add(&mut ids, id);
                 }
             }
-            ast::Stmt::AttrStmt(ast::AttrStmt::Graph(attr_list)) => {
-                for element in &attr_list.elems {
-                    for elem in &element.elems {
-                        if elem.0 == "label" {
-                            *label = Some(elem.1.trim_matches('"').to_string());
-                        } else if elem.0 == "style" {
-                            *style = Some(elem.1.trim_matches('"').to_string());
-                        }
-                    }
+            ast::Stmt::Subgraph(nested) => {
+                let nested =
+                    subgraph_endpoint_ids(nested, graph, path_prefix, defaults.clone(), state);
+                for (id, _) in &nested {
+                    add(&mut ids, id);
                 }
             }
-            _ => {}
         }
     }
+    ids
 }
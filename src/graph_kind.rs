@@ -0,0 +1,34 @@
+/// GraphKind tells a `digraph {}` apart from an undirected `graph {}`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum GraphKind {
+    #[default]
+    Directed, // digraph
+    Undirected, // graph
+}
+
+impl GraphKind {
+    // Build from the dot-parser `is_digraph` flag
+    pub const fn from_is_digraph(is_digraph: bool) -> Self {
+        if is_digraph {
+            GraphKind::Directed
+        } else {
+            GraphKind::Undirected
+        }
+    }
+
+    // Keyword for DOT output
+    pub const fn keyword(self) -> &'static str {
+        match self {
+            GraphKind::Directed => "digraph",
+            GraphKind::Undirected => "graph",
+        }
+    }
+
+    // Edge operator for DOT output
+    pub const fn edge_op(self) -> &'static str {
+        match self {
+            GraphKind::Directed => "->",
+            GraphKind::Undirected => "--",
+        }
+    }
+}
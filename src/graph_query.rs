@@ -0,0 +1,182 @@
+//! Lookups over GraphData: find nodes at any depth, neighbours and lineage.
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{
+    edge_renderer::EdgeData,
+    graph_data::{GraphData, NodeData},
+};
+
+impl GraphData {
+    /// Find a node by its (path-prefixed) id, searching all subgraphs
+    pub fn find_node(&self, id: &str) -> Option<&NodeData> {
+        self.all_nodes().find(|node| node.id == id)
+    }
+
+    /// Iterate over the nodes of this graph and all nested subgraphs, depth first
+    pub fn all_nodes(&self) -> AllNodes<'_> {
+        AllNodes {
+            nodes: self.nodes.iter(),
+            subgraphs: vec![self.subgraphs.iter()],
+        }
+    }
+
+    /// Build an index for neighbour and lineage queries
+    pub fn index(&self) -> GraphIndex<'_> {
+        GraphIndex::new(self)
+    }
+}
+
+/// Iterator over every node in a graph, see [`GraphData::all_nodes`]
+pub struct AllNodes<'a> {
+    nodes: std::slice::Iter<'a, NodeData>,
+    /// Subgraphs still to visit, one iterator per nesting level
+    subgraphs: Vec<std::slice::Iter<'a, GraphData>>,
+}
+
+impl<'a> Iterator for AllNodes<'a> {
+    type Item = &'a NodeData;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(node) = self.nodes.next() {
+                return Some(node);
+            }
+            // Move on to the next subgraph, climbing out of exhausted levels
+            let subgraph = loop {
+                match self.subgraphs.last_mut()?.next() {
+                    Some(subgraph) => break subgraph,
+                    None => {
+                        self.subgraphs.pop();
+                    }
+                }
+            };
+            self.nodes = subgraph.nodes.iter();
+            self.subgraphs.push(subgraph.subgraphs.iter());
+        }
+    }
+}
+
+/// Read-only index over a [`GraphData`], built once and queried by node id.
+///
+/// Edges are followed from `source` to `target` as written, for undirected graphs too.
+/// Ids are the path-prefixed node ids used in [`GraphData`].
+pub struct GraphIndex<'a> {
+    graph: &'a GraphData,
+    nodes: HashMap<&'a str, IndexedNode<'a>>,
+    outgoing: HashMap<&'a str, Vec<&'a EdgeData>>,
+    incoming: HashMap<&'a str, Vec<&'a EdgeData>>,
+}
+
+struct IndexedNode<'a> {
+    node: &'a NodeData,
+    /// Subgraphs containing the node, outermost first
+    path: Vec<&'a GraphData>,
+}
+
+impl<'a> GraphIndex<'a> {
+    /// Index all nodes and edges of `graph`
+    pub fn new(graph: &'a GraphData) -> Self {
+        let mut nodes = HashMap::new();
+        index_nodes(graph, &mut Vec::new(), &mut nodes);
+
+        let mut outgoing: HashMap<&str, Vec<&EdgeData>> = HashMap::new();
+        let mut incoming: HashMap<&str, Vec<&EdgeData>> = HashMap::new();
+        for edge in &graph.edges {
+            outgoing.entry(&edge.source).or_default().push(edge);
+            incoming.entry(&edge.target).or_default().push(edge);
+        }
+
+        GraphIndex {
+            graph,
+            nodes,
+            outgoing,
+            incoming,
+        }
+    }
+
+    /// The indexed graph
+    pub fn graph(&self) -> &'a GraphData {
+        self.graph
+    }
+
+    /// Find a node by id
+    pub fn node(&self, id: &str) -> Option<&'a NodeData> {
+        self.nodes.get(id).map(|entry| entry.node)
+    }
+
+    /// The subgraphs containing a node, outermost first. Empty for top level nodes,
+    /// `None` for unknown ids.
+    pub fn subgraph_path(&self, id: &str) -> Option<&[&'a GraphData]> {
+        self.nodes.get(id).map(|entry| entry.path.as_slice())
+    }
+
+    /// Edges leaving a node, in document order
+    pub fn out_edges(&self, id: &str) -> &[&'a EdgeData] {
+        self.outgoing.get(id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Edges arriving at a node, in document order
+    pub fn in_edges(&self, id: &str) -> &[&'a EdgeData] {
+        self.incoming.get(id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Targets of the node's outgoing edges, without duplicates
+    pub fn successors(&self, id: &str) -> Vec<&'a str> {
+        dedup(self.out_edges(id).iter().map(|edge| edge.target.as_str()))
+    }
+
+    /// Sources of the node's incoming edges, without duplicates
+    pub fn predecessors(&self, id: &str) -> Vec<&'a str> {
+        dedup(self.in_edges(id).iter().map(|edge| edge.source.as_str()))
+    }
+
+    /// Every node reachable from `id`, nearest first. `id` itself is not included.
+    pub fn descendants(&self, id: &str) -> Vec<&'a str> {
+        self.reachable(id, |node| self.successors(node))
+    }
+
+    /// Every node that can reach `id`, nearest first. `id` itself is not included.
+    pub fn ancestors(&self, id: &str) -> Vec<&'a str> {
+        self.reachable(id, |node| self.predecessors(node))
+    }
+
+    // Breadth-first walk using `next` to find the neighbours of a node
+    fn reachable(&self, id: &str, next: impl Fn(&str) -> Vec<&'a str>) -> Vec<&'a str> {
+        let mut seen = HashSet::from([id]);
+        let mut queue = VecDeque::from([id]);
+        let mut found = Vec::new();
+        while let Some(current) = queue.pop_front() {
+            for neighbour in next(current) {
+                if seen.insert(neighbour) {
+                    found.push(neighbour);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+        found
+    }
+}
+
+fn index_nodes<'a>(
+    graph: &'a GraphData,
+    path: &mut Vec<&'a GraphData>,
+    nodes: &mut HashMap<&'a str, IndexedNode<'a>>,
+) {
+    for node in &graph.nodes {
+        nodes.entry(&node.id).or_insert_with(|| IndexedNode {
+            node,
+            path: path.clone(),
+        });
+    }
+    for subgraph in &graph.subgraphs {
+        path.push(subgraph);
+        index_nodes(subgraph, path, nodes);
+        path.pop();
+    }
+}
+
+// Keep the first occurrence of each id
+fn dedup<'a>(ids: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut seen = HashSet::new();
+    ids.filter(|id| seen.insert(*id)).collect()
+}
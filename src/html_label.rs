@@ -0,0 +1,249 @@
+//! Graphviz HTML-like labels (`label=<<TABLE>...</TABLE>>`) parsed into a safe element tree.
+//!
+//! Only the Graphviz subset is kept: tables, text styling, fonts and line breaks.
+//! Unknown tags are dropped (their text is kept) and nothing is ever passed to the
+//! DOM as raw HTML.
+use crate::attributes::Attributes;
+
+/// A piece of an HTML-like label
+#[derive(Clone, Debug, PartialEq)]
+pub enum HtmlNode {
+    Text(String),
+    Element(HtmlElement),
+}
+
+/// A supported element with its attributes (lowercase names) and content
+#[derive(Clone, Debug, PartialEq)]
+pub struct HtmlElement {
+    pub tag: HtmlTag,
+    pub attributes: Attributes,
+    pub children: Vec<HtmlNode>,
+}
+
+impl HtmlElement {
+    /// The `PORT` attribute, which edges can attach to like a record field
+    pub fn port(&self) -> Option<&str> {
+        self.attributes.get("port")
+    }
+
+    /// Child elements, skipping text (such as whitespace between table rows)
+    pub fn child_elements(&self) -> impl Iterator<Item = &HtmlElement> {
+        self.children.iter().filter_map(|child| match child {
+            HtmlNode::Element(element) => Some(element),
+            HtmlNode::Text(_) => None,
+        })
+    }
+}
+
+/// HtmlTag is an element of the Graphviz HTML-like label subset
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HtmlTag {
+    Table,
+    Tr,
+    Td,
+    Font,
+    B,
+    I,
+    U,
+    O, // Overline
+    S,
+    Sub,
+    Sup,
+    Br,
+    Hr,
+    Vr,
+}
+
+impl HtmlTag {
+    // Parse from a tag name, case insensitive
+    pub fn from_slice(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "table" => Some(HtmlTag::Table),
+            "tr" => Some(HtmlTag::Tr),
+            "td" => Some(HtmlTag::Td),
+            "font" => Some(HtmlTag::Font),
+            "b" => Some(HtmlTag::B),
+            "i" => Some(HtmlTag::I),
+            "u" => Some(HtmlTag::U),
+            "o" => Some(HtmlTag::O),
+            "s" => Some(HtmlTag::S),
+            "sub" => Some(HtmlTag::Sub),
+            "sup" => Some(HtmlTag::Sup),
+            "br" => Some(HtmlTag::Br),
+            "hr" => Some(HtmlTag::Hr),
+            "vr" => Some(HtmlTag::Vr),
+            _ => None,
+        }
+    }
+
+    // Elements that never have content
+    const fn is_void(self) -> bool {
+        matches!(self, HtmlTag::Br | HtmlTag::Hr | HtmlTag::Vr)
+    }
+}
+
+/// Parse the text between the outer `<` `>` of an HTML-like label.
+/// Unclosed elements are closed at the end, stray closing tags are ignored.
+pub fn parse_html_label(html: &str) -> Vec<HtmlNode> {
+    let mut root = Vec::new();
+    let mut open: Vec<HtmlElement> = Vec::new();
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment
+                .find("-->")
+                .map(|end| &comment[end + 3..])
+                .unwrap_or("");
+            continue;
+        }
+
+        let Some(tag_source) = rest.strip_prefix('<') else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            let text = decode_entities(&rest[..end]);
+            append(&mut open, &mut root, HtmlNode::Text(text));
+            rest = &rest[end..];
+            continue;
+        };
+        let Some(end) = tag_source.find('>') else {
+            append(&mut open, &mut root, HtmlNode::Text(decode_entities(rest)));
+            break;
+        };
+        let tag = tag_source[..end].trim();
+        rest = &tag_source[end + 1..];
+
+        if let Some(name) = tag.strip_prefix('/') {
+            close(&mut open, &mut root, name.trim());
+            continue;
+        }
+
+        let self_closing = tag.ends_with('/');
+        let (name, attributes) = parse_tag(tag.trim_end_matches('/'));
+        let Some(tag) = HtmlTag::from_slice(name) else {
+            continue;
+        };
+        let element = HtmlElement {
+            tag,
+            attributes,
+            children: Vec::new(),
+        };
+        if self_closing || tag.is_void() {
+            append(&mut open, &mut root, HtmlNode::Element(element));
+        } else {
+            open.push(element);
+        }
+    }
+
+    while let Some(element) = open.pop() {
+        append(&mut open, &mut root, HtmlNode::Element(element));
+    }
+    root
+}
+
+// Add a node to the innermost open element, or to the root
+fn append(open: &mut [HtmlElement], root: &mut Vec<HtmlNode>, node: HtmlNode) {
+    match open.last_mut() {
+        Some(parent) => parent.children.push(node),
+        None => root.push(node),
+    }
+}
+
+// Close the innermost open element with this tag, and everything opened inside it
+fn close(open: &mut Vec<HtmlElement>, root: &mut Vec<HtmlNode>, name: &str) {
+    let Some(tag) = HtmlTag::from_slice(name) else {
+        return;
+    };
+    if !open.iter().any(|element| element.tag == tag) {
+        return;
+    }
+    while let Some(element) = open.pop() {
+        let done = element.tag == tag;
+        append(open, root, HtmlNode::Element(element));
+        if done {
+            break;
+        }
+    }
+}
+
+// Split `name key="value" key='value' key=value` into the name and its attributes
+fn parse_tag(tag: &str) -> (&str, Attributes) {
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let (name, mut rest) = tag.split_at(name_end);
+    let mut attributes = Attributes::new();
+
+    loop {
+        rest = rest.trim_start();
+        let key_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        if key_end == 0 {
+            break;
+        }
+        let key = rest[..key_end].to_ascii_lowercase();
+        rest = rest[key_end..].trim_start();
+
+        let Some(value_source) = rest.strip_prefix('=') else {
+            // Attribute without a value
+            attributes.insert(key, "");
+            continue;
+        };
+        let value_source = value_source.trim_start();
+        let (value, remaining) = match value_source.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let inner = &value_source[1..];
+                let end = inner.find(quote).unwrap_or(inner.len());
+                (&inner[..end], inner.get(end + 1..).unwrap_or(""))
+            }
+            _ => {
+                let end = value_source
+                    .find(char::is_whitespace)
+                    .unwrap_or(value_source.len());
+                value_source.split_at(end)
+            }
+        };
+        attributes.insert(key, decode_entities(value));
+        rest = remaining;
+    }
+    (name, attributes)
+}
+
+// Replace the character entities Graphviz understands
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => '\u{a0}',
+                _ => {
+                    let code = match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => entity.strip_prefix('#')?.parse().ok(),
+                    };
+                    char::from_u32(code?)?
+                }
+            };
+            Some((c, end + 1))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,500 @@
+//! Watch the nodes of an edge arena and report the ones that moved or changed size
+use crate::node_shape::NodeShape;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::js_sys::{Array, Function};
+use web_sys::{
+    Element, HtmlElement, MutationObserver, MutationObserverInit, MutationRecord, Node,
+    ResizeObserver,
+};
+
+/// Attribute marking the element an arena draws its edges in
+pub const EDGE_LAYER_ATTRIBUTE: &str = "data-edge-layer";
+
+/// Box of a node in its arena's [`ArenaSpace`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NodeBox {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Everything the edges of one node attach to, measured together once a frame
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeLayout {
+    /// Box of the node element
+    pub bounds: NodeBox,
+    /// Outline drawn by the node's `[data-shape]` element, with that element's box
+    pub shape: Option<(NodeShape, NodeBox)>,
+    /// Boxes of the node's `[data-port]` elements, by port name
+    pub ports: HashMap<String, NodeBox>,
+}
+
+/// The coordinate space of an arena: CSS pixels from the top left of its content,
+/// the space its absolutely positioned children are laid out in.
+///
+/// Client rects are scaled by any `transform` on the arena or its ancestors and
+/// shifted by every scroll offset around it; converting through this space undoes both,
+/// so boxes stay put when a scroll pane scrolls or a zoomed container zooms.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ArenaSpace {
+    /// Client position of the space's origin
+    pub left: f64,
+    pub top: f64,
+    /// Client pixels per arena pixel
+    pub scale_x: f64,
+    pub scale_y: f64,
+}
+
+impl ArenaSpace {
+    /// Space of `arena` as it is laid out now
+    pub fn of(arena: &Element) -> Self {
+        let rect = arena.get_bounding_client_rect();
+        // The layout size ignores transforms, the client rect doesn't
+        let (width, height) = arena
+            .dyn_ref::<HtmlElement>()
+            .map(|el| (el.offset_width(), el.offset_height()))
+            .unwrap_or_default();
+        let scale = |shown: f64, laid_out: i32| {
+            if laid_out > 0 && shown > 0.0 {
+                shown / laid_out as f64
+            } else {
+                1.0
+            }
+        };
+        let scale_x = scale(rect.width(), width);
+        let scale_y = scale(rect.height(), height);
+        // Children start inside the border and scroll with the arena's content
+        ArenaSpace {
+            left: rect.left() + f64::from(arena.client_left() - arena.scroll_left()) * scale_x,
+            top: rect.top() + f64::from(arena.client_top() - arena.scroll_top()) * scale_y,
+            scale_x,
+            scale_y,
+        }
+    }
+
+    /// Box in this space of a client rect
+    pub fn local(&self, left: f64, top: f64, width: f64, height: f64) -> NodeBox {
+        NodeBox {
+            x: (left - self.left) / self.scale_x,
+            y: (top - self.top) / self.scale_y,
+            width: width / self.scale_x,
+            height: height / self.scale_y,
+        }
+    }
+
+    /// Box of `el` in this space
+    pub fn element_box(&self, el: &Element) -> NodeBox {
+        let rect = el.get_bounding_client_rect();
+        self.local(rect.left(), rect.top(), rect.width(), rect.height())
+    }
+}
+
+/// Where the nodes of one arena live in the DOM.
+///
+/// Node elements get DOM ids prefixed per arena, so graphs sharing a page can use the
+/// same node ids. Lookups only match elements inside the arena, and fall back to the
+/// raw node id for nodes rendered with it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeScope {
+    prefix: String,
+}
+
+impl NodeScope {
+    /// Scope whose DOM ids start with `prefix`
+    pub fn new(prefix: impl Into<String>) -> Self {
+        NodeScope {
+            prefix: prefix.into(),
+        }
+    }
+
+    /// DOM id of node `id` in this scope
+    pub fn dom_id(&self, id: &str) -> String {
+        format!("{}{id}", self.prefix)
+    }
+
+    /// Element of node `id` inside `arena`
+    pub fn find(&self, arena: &Element, id: &str) -> Option<Element> {
+        let document = arena.owner_document()?;
+        let inside = |el: &Element| arena.contains(Some(el));
+        document
+            .get_element_by_id(&self.dom_id(id))
+            .filter(inside)
+            .or_else(|| document.get_element_by_id(id).filter(inside))
+            .or_else(|| {
+                // Another element on the page took the raw id first
+                let id = id.replace('\\', "\\\\").replace('"', "\\\"");
+                arena
+                    .query_selector(&format!("[id=\"{id}\"]"))
+                    .ok()
+                    .flatten()
+            })
+    }
+}
+
+/// The parts of a DOM node that deciding whether a mutation matters looks at
+pub(crate) trait DomNode: Sized {
+    fn parent_node(&self) -> Option<Self>;
+    /// Whether this is an element carrying [`EDGE_LAYER_ATTRIBUTE`]
+    fn is_edge_layer(&self) -> bool;
+}
+
+impl DomNode for Node {
+    fn parent_node(&self) -> Option<Self> {
+        Node::parent_node(self)
+    }
+
+    fn is_edge_layer(&self) -> bool {
+        self.dyn_ref::<Element>()
+            .is_some_and(|el| el.has_attribute(EDGE_LAYER_ATTRIBUTE))
+    }
+}
+
+/// Whether a DOM mutation at `target` can move or resize nodes. Everything inside an
+/// edge layer is drawn from the node boxes, so changes there never need a new measure.
+pub(crate) fn moves_nodes<N: DomNode>(target: N) -> bool {
+    let mut node = Some(target);
+    while let Some(current) = node {
+        if current.is_edge_layer() {
+            return false;
+        }
+        node = current.parent_node();
+    }
+    true
+}
+
+/// Watches an arena and its nodes for size, position and DOM changes.
+///
+/// Changes are batched per animation frame: once a frame every node is measured and
+/// `on_change` gets the ids of the nodes whose layout changed, moved or disappeared,
+/// along with the layouts of all nodes; frames where nothing moved report no ids.
+/// Edges read nothing but these layouts, so the DOM is read once per node and frame.
+/// The first frame reports every node. Watching stops when this is dropped.
+pub struct LayoutObserver {
+    shared: Rc<Shared>,
+    resize: ResizeObserver,
+    mutation: MutationObserver,
+    schedule: Closure<dyn FnMut()>,
+    _mutations: Closure<dyn FnMut(Array)>,
+    // Kept alive for the animation frames requested by `schedule`
+    _frame: Closure<dyn FnMut()>,
+}
+
+// Called once a frame with the ids of the changed nodes and the layouts of all nodes
+type OnChange = Box<dyn FnMut(&[String], &HashMap<String, NodeLayout>)>;
+
+// State shared by the observer callbacks
+struct Shared {
+    arena: Element,
+    scope: NodeScope,
+    node_ids: RefCell<Vec<String>>,
+    layouts: RefCell<HashMap<String, NodeLayout>>,
+    /// Node and port elements currently watched for resizes, by node id
+    observed: RefCell<HashMap<String, Vec<Element>>>,
+    resize: RefCell<Option<ResizeObserver>>,
+    /// Animation frame requested and not yet run
+    frame_request: Cell<Option<i32>>,
+    on_change: RefCell<OnChange>,
+}
+
+impl LayoutObserver {
+    /// Start watching `arena` and the nodes with `node_ids` inside it, found through `scope`
+    pub fn new(
+        arena: Element,
+        scope: NodeScope,
+        node_ids: Vec<String>,
+        on_change: impl FnMut(&[String], &HashMap<String, NodeLayout>) + 'static,
+    ) -> Result<Self, JsValue> {
+        let window = web_sys::window().ok_or("No window")?;
+        let shared = Rc::new(Shared {
+            arena,
+            scope,
+            node_ids: RefCell::new(node_ids),
+            layouts: RefCell::default(),
+            observed: RefCell::default(),
+            resize: RefCell::default(),
+            frame_request: Cell::new(None),
+            on_change: RefCell::new(Box::new(on_change)),
+        });
+
+        let frame = {
+            let shared = shared.clone();
+            Closure::<dyn FnMut()>::new(move || shared.measure())
+        };
+        let schedule = {
+            let shared = shared.clone();
+            let frame: Function = frame.as_ref().unchecked_ref::<Function>().clone();
+            let window = window.clone();
+            Closure::<dyn FnMut()>::new(move || {
+                if shared.frame_request.get().is_none() {
+                    shared
+                        .frame_request
+                        .set(window.request_animation_frame(&frame).ok());
+                }
+            })
+        };
+        let callback: &Function = schedule.as_ref().unchecked_ref();
+
+        let resize = ResizeObserver::new(callback)?;
+        resize.observe(&shared.arena);
+        shared.resize.replace(Some(resize.clone()));
+
+        // Nodes appearing, disappearing or changing class or content. Drawing the edges
+        // mutates the edge layer, which would otherwise set off a measure every frame.
+        let mutations = {
+            let schedule = callback.clone();
+            Closure::<dyn FnMut(Array)>::new(move |records: Array| {
+                let moved = records.iter().any(|record| {
+                    record
+                        .dyn_into::<MutationRecord>()
+                        .ok()
+                        .and_then(|record| record.target())
+                        .is_some_and(moves_nodes)
+                });
+                if moved {
+                    let _ = schedule.call0(&JsValue::NULL);
+                }
+            })
+        };
+        let mutation = MutationObserver::new(mutations.as_ref().unchecked_ref())?;
+        let init = MutationObserverInit::new();
+        init.set_child_list(true);
+        init.set_subtree(true);
+        init.set_attributes(true);
+        init.set_character_data(true);
+        mutation.observe_with_options(&shared.arena, &init)?;
+
+        // Scrolling anywhere inside the arena moves nodes without resizing them
+        shared
+            .arena
+            .add_event_listener_with_callback_and_bool("scroll", callback, true)?;
+        window.add_event_listener_with_callback("resize", callback)?;
+
+        let observer = LayoutObserver {
+            shared,
+            resize,
+            mutation,
+            schedule,
+            _mutations: mutations,
+            _frame: frame,
+        };
+        observer.schedule();
+        Ok(observer)
+    }
+
+    /// Watch these nodes instead, measuring them on the next frame
+    pub fn set_node_ids(&self, node_ids: Vec<String>) {
+        if *self.shared.node_ids.borrow() != node_ids {
+            self.shared.node_ids.replace(node_ids);
+            self.schedule();
+        }
+    }
+
+    /// Measure the nodes again on the next animation frame
+    pub fn schedule(&self) {
+        let callback: &Function = self.schedule.as_ref().unchecked_ref();
+        let _ = callback.call0(&JsValue::NULL);
+    }
+
+    /// Box of node `id` as of the last frame
+    pub fn node_box(&self, id: &str) -> Option<NodeBox> {
+        self.shared
+            .layouts
+            .borrow()
+            .get(id)
+            .map(|layout| layout.bounds)
+    }
+}
+
+impl Drop for LayoutObserver {
+    fn drop(&mut self) {
+        let callback: &Function = self.schedule.as_ref().unchecked_ref();
+        self.resize.disconnect();
+        self.mutation.disconnect();
+        self.shared.resize.take();
+        let _ = self
+            .shared
+            .arena
+            .remove_event_listener_with_callback_and_bool("scroll", callback, true);
+        if let Some(window) = web_sys::window() {
+            let _ = window.remove_event_listener_with_callback("resize", callback);
+            if let Some(request) = self.shared.frame_request.take() {
+                let _ = window.cancel_animation_frame(request);
+            }
+        }
+    }
+}
+
+impl Shared {
+    // Measure every node and report the ones whose layout changed since the last frame
+    fn measure(&self) {
+        self.frame_request.set(None);
+        let space = ArenaSpace::of(&self.arena);
+
+        let mut layouts = HashMap::new();
+        for id in self.node_ids.borrow().iter() {
+            let Some(el) = self.scope.find(&self.arena, id) else {
+                continue;
+            };
+            let (layout, ports) = measure_node(&space, &el);
+            self.observe(id, el, ports);
+            layouts.insert(id.clone(), layout);
+        }
+        // Stop watching elements of nodes that are gone
+        if let Some(resize) = self.resize.borrow().as_ref() {
+            self.observed.borrow_mut().retain(|id, elements| {
+                let present = layouts.contains_key(id);
+                if !present {
+                    for el in elements.iter() {
+                        resize.unobserve(el);
+                    }
+                }
+                present
+            });
+        }
+
+        let previous = self.layouts.replace(layouts);
+        let layouts = self.layouts.borrow();
+        let mut changed: Vec<String> = layouts
+            .iter()
+            .filter(|(id, layout)| previous.get(*id) != Some(layout))
+            .map(|(id, _)| id.clone())
+            .chain(
+                previous
+                    .keys()
+                    .filter(|id| !layouts.contains_key(*id))
+                    .cloned(),
+            )
+            .collect();
+        changed.sort();
+        (self.on_change.borrow_mut())(&changed, &layouts);
+    }
+
+    // Watch the element of node `id` and its port elements for resizes, moving over to
+    // the elements that replaced them
+    fn observe(&self, id: &str, el: Element, ports: Vec<Element>) {
+        let resize = self.resize.borrow();
+        let Some(resize) = resize.as_ref() else {
+            return;
+        };
+        let mut elements = vec![el];
+        elements.extend(ports);
+        let old = self
+            .observed
+            .borrow_mut()
+            .insert(id.to_string(), elements.clone())
+            .unwrap_or_default();
+        for el in elements.iter().filter(|el| !old.contains(el)) {
+            resize.observe(el);
+        }
+        for el in old.iter().filter(|el| !elements.contains(el)) {
+            resize.unobserve(el);
+        }
+    }
+}
+
+// Layout of the node element `el`, along with its port elements
+fn measure_node(space: &ArenaSpace, el: &Element) -> (NodeLayout, Vec<Element>) {
+    let shape = el
+        .query_selector("[data-shape]")
+        .ok()
+        .flatten()
+        .and_then(|shape_el| {
+            let shape = NodeShape::from_slice(&shape_el.get_attribute("data-shape")?)?;
+            Some((shape, space.element_box(&shape_el)))
+        });
+
+    let mut ports = HashMap::new();
+    let mut port_elements = Vec::new();
+    if let Ok(found) = el.query_selector_all("[data-port]") {
+        for index in 0..found.length() {
+            let Some(port_el) = found
+                .item(index)
+                .and_then(|node| node.dyn_into::<Element>().ok())
+            else {
+                continue;
+            };
+            let Some(name) = port_el.get_attribute("data-port") else {
+                continue;
+            };
+            // The first element with a port name is the one edges attach to
+            if let std::collections::hash_map::Entry::Vacant(entry) = ports.entry(name) {
+                entry.insert(space.element_box(&port_el));
+                port_elements.push(port_el);
+            }
+        }
+    }
+
+    let layout = NodeLayout {
+        bounds: space.element_box(el),
+        shape,
+        ports,
+    };
+    (layout, port_elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{moves_nodes, DomNode};
+
+    // A DOM tree as a list of (parent, is edge layer) entries, indexed by position
+    struct Tree(Vec<(Option<usize>, bool)>);
+
+    #[derive(Clone, Copy)]
+    struct TreeNode<'a>(&'a Tree, usize);
+
+    impl DomNode for TreeNode<'_> {
+        fn parent_node(&self) -> Option<Self> {
+            self.0 .0[self.1].0.map(|parent| TreeNode(self.0, parent))
+        }
+
+        fn is_edge_layer(&self) -> bool {
+            self.0 .0[self.1].1
+        }
+    }
+
+    // An arena with a cluster holding a node, and the svg its edges are drawn in
+    const ARENA: usize = 0;
+    const CLUSTER: usize = 1;
+    const NODE: usize = 2;
+    const NODE_TEXT: usize = 3;
+    const EDGE_LAYER: usize = 4;
+    const EDGE_GROUP: usize = 5;
+    const EDGE_PATH: usize = 6;
+    const EDGE_LABEL_TEXT: usize = 7;
+
+    fn arena() -> Tree {
+        Tree(vec![
+            (None, false),
+            (Some(ARENA), false),
+            (Some(CLUSTER), false),
+            (Some(NODE), false),
+            (Some(ARENA), true),
+            (Some(EDGE_LAYER), false),
+            (Some(EDGE_GROUP), false),
+            (Some(EDGE_GROUP), false),
+        ])
+    }
+
+    #[test]
+    fn applying_edge_geometry_does_not_trigger_a_measure() {
+        let tree = arena();
+        // New edges added to the layer, a path's `d` changed, a label's text replaced
+        for target in [EDGE_LAYER, EDGE_GROUP, EDGE_PATH, EDGE_LABEL_TEXT] {
+            assert!(!moves_nodes(TreeNode(&tree, target)), "{target}");
+        }
+    }
+
+    #[test]
+    fn node_changes_trigger_a_measure() {
+        let tree = arena();
+        // Nodes added to the arena, a cluster collapsed, a node's class or text changed
+        for target in [ARENA, CLUSTER, NODE, NODE_TEXT] {
+            assert!(moves_nodes(TreeNode(&tree, target)), "{target}");
+        }
+    }
+}
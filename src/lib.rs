@@ -1,7 +1,23 @@
 //! Exportable library for dioxus-plumb utils and components
+pub mod algorithms;
+pub mod attributes;
 pub mod dot_renderer;
+pub mod dot_writer;
+pub mod edge_dir;
+pub mod edge_port;
 pub mod edge_renderer;
+pub mod edge_router;
+pub mod edge_routing;
+pub mod error;
+pub mod graph_builder;
 pub mod graph_data;
+pub mod graph_kind;
+pub mod graph_query;
+pub mod html_label;
+pub mod layout_observer;
 pub mod node_renderer;
+pub mod node_shape;
 pub mod perfect_arrows;
+mod preprocess;
 pub mod rankdir;
+pub mod record;
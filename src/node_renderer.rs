@@ -1,5 +1,9 @@
 use crate::dot_renderer::DotNodeRenderer;
+use crate::edge_renderer::node_dom_id;
 use crate::graph_data::NodeData;
+use crate::html_label::{HtmlElement, HtmlNode, HtmlTag};
+use crate::node_shape::NodeShape;
+use crate::record::RecordField;
 use dioxus::prelude::*;
 
 // A simple default renderer for DOT nodes
@@ -7,6 +11,16 @@ pub struct DefaultNodeRenderer;
 
 impl DotNodeRenderer for DefaultNodeRenderer {
     fn render_node(&self, node: &NodeData) -> Element {
+        if node.record.is_some() {
+            return rsx! { RecordNode { node: node.clone() } };
+        }
+        if node.html_label.is_some() {
+            return rsx! { HtmlLabelNode { node: node.clone() } };
+        }
+        if let Some(shape) = NodeShape::from_attributes(&node.attributes) {
+            return rsx! { ShapedNode { node: node.clone(), shape } };
+        }
+
         let label = node.label.as_deref().unwrap_or(&node.id);
 
         rsx! {
@@ -18,11 +32,373 @@ impl DotNodeRenderer for DefaultNodeRenderer {
     }
 }
 
+/// Renders a `shape=record` / `Mrecord` node as nested flexbox fields.
+/// Fields with a port get `data-port` and the [`node_dom_id`] of `{node id}:{port}`,
+/// so edges like `a:f0 -> b` attach to the field.
+#[component]
+pub fn RecordNode(node: NodeData) -> Element {
+    let rounded = if node.attributes.get("shape") == Some("Mrecord") {
+        "rounded-lg"
+    } else {
+        "rounded-none"
+    };
+
+    rsx! {
+        div {
+            class: "bg-white border border-gray-300 m-2 shadow overflow-hidden w-fit {rounded}",
+            RecordFields {
+                node_id: node.id.clone(),
+                fields: node.record.clone().unwrap_or_default(),
+                vertical: false
+            }
+        }
+    }
+}
+
+// Fields of one record level, groups flip between rows and columns like in Graphviz
+#[component]
+fn RecordFields(node_id: String, fields: Vec<RecordField>, vertical: bool) -> Element {
+    let direction = if vertical {
+        "flex-col divide-y"
+    } else {
+        "flex-row divide-x"
+    };
+
+    rsx! {
+        div {
+            class: "flex {direction} divide-gray-300 items-stretch h-full",
+            {fields.into_iter().map(|field| match field {
+                RecordField::Text { port, text } => rsx! {
+                    div {
+                        id: port.as_ref().map(|port| node_dom_id(&format!("{node_id}:{port}"))),
+                        "data-port": port,
+                        class: "flex flex-1 items-center justify-center px-2 py-1 text-sm text-center whitespace-pre",
+                        "{text}"
+                    }
+                },
+                RecordField::Group(fields) => rsx! {
+                    div {
+                        class: "flex-1",
+                        RecordFields { node_id: node_id.clone(), fields, vertical: !vertical }
+                    }
+                },
+            })}
+        }
+    }
+}
+
+/// Renders a node inside the outline of its Graphviz `shape`, drawn as an SVG that
+/// stretches with the label. The outline element carries `data-shape` so edges end
+/// on the outline instead of the bounding box. `color` and `fillcolor` are honored.
+#[component]
+pub fn ShapedNode(node: NodeData, shape: NodeShape) -> Element {
+    let label = node.label.clone().unwrap_or_else(|| node.id.clone());
+    let stroke = node
+        .attributes
+        .get("color")
+        .and_then(css_color)
+        .unwrap_or("#d1d5db")
+        .to_string();
+    let fill = node
+        .attributes
+        .get("fillcolor")
+        .and_then(css_color)
+        .unwrap_or("white")
+        .to_string();
+
+    // Room for the parts of the outline that cut into the box
+    let layout = match shape {
+        NodeShape::Box | NodeShape::Note => "px-3 py-2",
+        NodeShape::Ellipse => "px-6 py-3",
+        NodeShape::Circle | NodeShape::DoubleCircle => {
+            "p-3 aspect-square flex items-center justify-center"
+        }
+        NodeShape::Diamond => "px-8 py-5",
+        NodeShape::Parallelogram | NodeShape::Hexagon => "px-7 py-2",
+        NodeShape::Cylinder => "px-3 pt-5 pb-3",
+        NodeShape::Folder => "px-3 pt-4 pb-2",
+        NodeShape::Plaintext => "px-1",
+    };
+
+    rsx! {
+        div {
+            "data-shape": shape.to_str(),
+            class: "relative m-2 w-fit text-sm text-center {layout}",
+            if shape != NodeShape::Plaintext {
+                svg {
+                    class: "absolute inset-0 w-full h-full overflow-visible",
+                    "viewBox": "0 0 100 100",
+                    "preserveAspectRatio": "none",
+                    fill: "{fill}",
+                    stroke: "{stroke}",
+                    ShapeOutline { shape }
+                }
+            }
+            div {
+                class: "relative whitespace-pre",
+                "{label}"
+            }
+        }
+    }
+}
+
+// The outline of a shape in a 100x100 view box, stretched over the node
+#[component]
+fn ShapeOutline(shape: NodeShape) -> Element {
+    let points = |corners: &[(f64, f64)]| {
+        corners
+            .iter()
+            .map(|(x, y)| format!("{},{}", x * 100.0, y * 100.0))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    match shape {
+        NodeShape::Ellipse | NodeShape::Circle => rsx! {
+            ellipse { cx: 50, cy: 50, rx: 50, ry: 50, "vector-effect": "non-scaling-stroke" }
+        },
+        NodeShape::DoubleCircle => rsx! {
+            ellipse { cx: 50, cy: 50, rx: 50, ry: 50, "vector-effect": "non-scaling-stroke" }
+            ellipse { cx: 50, cy: 50, rx: 44, ry: 44, "vector-effect": "non-scaling-stroke" }
+        },
+        NodeShape::Cylinder => rsx! {
+            path {
+                d: "M0,8 A50,8 0 0,1 100,8 V92 A50,8 0 0,1 0,92 Z",
+                "vector-effect": "non-scaling-stroke"
+            }
+            path {
+                d: "M0,8 A50,8 0 0,0 100,8",
+                fill: "none",
+                "vector-effect": "non-scaling-stroke"
+            }
+        },
+        NodeShape::Note => rsx! {
+            polygon {
+                points: "0,0 88,0 100,15 100,100 0,100",
+                "vector-effect": "non-scaling-stroke"
+            }
+            polyline {
+                points: "88,0 88,15 100,15",
+                fill: "none",
+                "vector-effect": "non-scaling-stroke"
+            }
+        },
+        NodeShape::Folder => rsx! {
+            polygon {
+                points: "0,12 0,100 100,100 100,12 95,12 90,0 65,0 60,12",
+                "vector-effect": "non-scaling-stroke"
+            }
+        },
+        NodeShape::Plaintext => rsx! {},
+        NodeShape::Box | NodeShape::Diamond | NodeShape::Parallelogram | NodeShape::Hexagon => {
+            let points = points(shape.polygon().unwrap_or_default());
+            rsx! {
+                polygon { points, "vector-effect": "non-scaling-stroke" }
+            }
+        }
+    }
+}
+
+/// Renders a node with an HTML-like label (`label=<...>`) as real table and text markup.
+/// Like record fields, cells with a `PORT` get `data-port` and the [`node_dom_id`]
+/// of `{node id}:{port}` so edges attach to them.
+#[component]
+pub fn HtmlLabelNode(node: NodeData) -> Element {
+    // Graphviz users pair HTML tables with `shape=none` to draw only the table
+    let frame = match node.attributes.get("shape") {
+        Some("none" | "plaintext" | "plain") => "",
+        _ => "bg-white border border-gray-300 rounded p-2 shadow",
+    };
+
+    rsx! {
+        div {
+            class: "m-2 w-fit text-sm {frame}",
+            HtmlNodes {
+                node_id: node.id.clone(),
+                nodes: node.html_label.clone().unwrap_or_default(),
+                table: None
+            }
+        }
+    }
+}
+
+// Table settings inherited by the cells of a table
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct TableStyle {
+    cell_border: f64,
+    cell_padding: f64,
+}
+
+// A list of label nodes. `table` is set for the rows and cells of a table,
+// where text is only the whitespace between tags and is skipped.
+#[component]
+fn HtmlNodes(node_id: String, nodes: Vec<HtmlNode>, table: Option<TableStyle>) -> Element {
+    rsx! {
+        {nodes.into_iter().map(|node| match node {
+            HtmlNode::Text(_) if table.is_some() => rsx! {},
+            HtmlNode::Text(text) => rsx! { "{text}" },
+            HtmlNode::Element(element) => rsx! {
+                HtmlElementView { node_id: node_id.clone(), element, table }
+            },
+        })}
+    }
+}
+
+#[component]
+fn HtmlElementView(node_id: String, element: HtmlElement, table: Option<TableStyle>) -> Element {
+    let attributes = &element.attributes;
+    let number = |key: &str| {
+        attributes
+            .get(key)
+            .and_then(|v| v.trim().parse::<f64>().ok())
+    };
+    let count = |key: &str| {
+        attributes
+            .get(key)
+            .and_then(|v| v.trim().parse::<usize>().ok())
+    };
+    let color = attributes
+        .get("color")
+        .and_then(css_color)
+        .unwrap_or("black");
+    let background = attributes
+        .get("bgcolor")
+        .and_then(css_color)
+        .map(|bg| format!("background-color: {bg};"))
+        .unwrap_or_default();
+    let children = element.children.clone();
+
+    match element.tag {
+        HtmlTag::Table => {
+            let border = number("border").unwrap_or(1.0);
+            let style = TableStyle {
+                cell_border: number("cellborder").unwrap_or(border),
+                cell_padding: number("cellpadding").unwrap_or(2.0),
+            };
+            let spacing = number("cellspacing").unwrap_or(2.0);
+            rsx! {
+                table {
+                    style: "border: {border}px solid {color}; border-collapse: separate; border-spacing: {spacing}px; {background}",
+                    tbody {
+                        HtmlNodes { node_id, nodes: children, table: Some(style) }
+                    }
+                }
+            }
+        }
+        HtmlTag::Tr => rsx! {
+            tr {
+                HtmlNodes { node_id, nodes: children, table }
+            }
+        },
+        HtmlTag::Td => {
+            let style = table.unwrap_or(TableStyle {
+                cell_border: 1.0,
+                cell_padding: 2.0,
+            });
+            let border = number("border").unwrap_or(style.cell_border);
+            let padding = number("cellpadding").unwrap_or(style.cell_padding);
+            let align = match attributes
+                .get("align")
+                .map(str::to_ascii_lowercase)
+                .as_deref()
+            {
+                Some("left") => "left",
+                Some("right") => "right",
+                _ => "center",
+            };
+            let valign = match attributes
+                .get("valign")
+                .map(str::to_ascii_lowercase)
+                .as_deref()
+            {
+                Some("top") => "top",
+                Some("bottom") => "bottom",
+                _ => "middle",
+            };
+            let port = element.port().map(String::from);
+            let id = port
+                .as_ref()
+                .map(|port| node_dom_id(&format!("{node_id}:{port}")));
+            rsx! {
+                td {
+                    id,
+                    "data-port": port,
+                    colspan: count("colspan").unwrap_or(1),
+                    rowspan: count("rowspan").unwrap_or(1),
+                    style: "border: {border}px solid {color}; padding: {padding}px; text-align: {align}; vertical-align: {valign}; {background}",
+                    // Cell content is inline again
+                    HtmlNodes { node_id, nodes: children, table: None }
+                }
+            }
+        }
+        HtmlTag::Font => {
+            let mut style = String::new();
+            if let Some(color) = attributes.get("color").and_then(css_color) {
+                style.push_str(&format!("color: {color};"));
+            }
+            if let Some(size) = number("point-size") {
+                style.push_str(&format!("font-size: {size}pt;"));
+            }
+            if let Some(face) = attributes.get("face").filter(|face| is_font_family(face)) {
+                style.push_str(&format!("font-family: {face};"));
+            }
+            rsx! {
+                span {
+                    style,
+                    HtmlNodes { node_id, nodes: children, table: None }
+                }
+            }
+        }
+        HtmlTag::B => rsx! { b { HtmlNodes { node_id, nodes: children, table: None } } },
+        HtmlTag::I => rsx! { i { HtmlNodes { node_id, nodes: children, table: None } } },
+        HtmlTag::U => rsx! { u { HtmlNodes { node_id, nodes: children, table: None } } },
+        HtmlTag::O => rsx! {
+            span {
+                style: "text-decoration: overline;",
+                HtmlNodes { node_id, nodes: children, table: None }
+            }
+        },
+        HtmlTag::S => rsx! { s { HtmlNodes { node_id, nodes: children, table: None } } },
+        HtmlTag::Sub => rsx! { sub { HtmlNodes { node_id, nodes: children, table: None } } },
+        HtmlTag::Sup => rsx! { sup { HtmlNodes { node_id, nodes: children, table: None } } },
+        HtmlTag::Br => rsx! { br {} },
+        // A rule between rows spans the whole table
+        HtmlTag::Hr => rsx! {
+            tr {
+                td {
+                    colspan: 1000,
+                    style: "border-top: 1px solid {color}; padding: 0;"
+                }
+            }
+        },
+        HtmlTag::Vr => rsx! {
+            td { style: "border-left: 1px solid {color}; padding: 0;" }
+        },
+    }
+}
+
+// Graphviz colors are names or `#rrggbb[aa]`; anything else is not passed on to CSS
+fn css_color(value: &str) -> Option<&str> {
+    let value = value.trim();
+    (!value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '#'))
+        .then_some(value)
+}
+
+fn is_font_family(face: &str) -> bool {
+    face.chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, ' ' | '-' | '_' | ','))
+}
+
 // A fancy renderer with different styles based on node attributes
 pub struct FancyNodeRenderer;
 
 impl DotNodeRenderer for FancyNodeRenderer {
     fn render_node(&self, node: &NodeData) -> Element {
+        // An explicit shape says more than the node's name does
+        if let Some(shape) = NodeShape::from_attributes(&node.attributes) {
+            return rsx! { ShapedNode { node: node.clone(), shape } };
+        }
+
         let label = node.label.as_deref().unwrap_or(&node.id);
 
         // Different styles based on node ID or label
@@ -64,6 +440,29 @@ impl DotNodeRenderer for InteractiveNodeRenderer {
         let label = node.label.as_deref().unwrap_or(&node.id);
         let on_click = self.on_node_click;
 
+        // Records, HTML labels and shapes keep their own look, only made clickable
+        let content = if node.record.is_some() {
+            Some(rsx! { RecordNode { node: node.clone() } })
+        } else if node.html_label.is_some() {
+            Some(rsx! { HtmlLabelNode { node: node.clone() } })
+        } else {
+            NodeShape::from_attributes(&node.attributes)
+                .map(|shape| rsx! { ShapedNode { node: node.clone(), shape } })
+        };
+        if let Some(content) = content {
+            return rsx! {
+                div {
+                    class: "cursor-pointer w-fit mx-auto",
+                    onclick: move |_| {
+                        if let Some(handler) = &on_click {
+                            handler.call(node_id.clone());
+                        }
+                    },
+                    {content}
+                }
+            };
+        }
+
         // More compact styling that works well with w-fit
         rsx! {
             div {
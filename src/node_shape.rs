@@ -0,0 +1,116 @@
+use crate::attributes::Attributes;
+use crate::perfect_arrows::{Pos2, Shape, Vec2};
+
+/// NodeShape is the outline drawn around a node.
+/// It corresponds to the 'shape' attribute in DOT language.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeShape {
+    Box,
+    Ellipse,
+    Circle,
+    DoubleCircle,
+    Diamond,
+    Parallelogram,
+    Hexagon,
+    Cylinder,
+    Note,
+    Folder,
+    Plaintext, // No outline, only the label
+}
+
+/// Corners of the polygon shapes, as fractions of the node width and height
+/// from its top-left corner
+const BOX: &[(f64, f64)] = &[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+const DIAMOND: &[(f64, f64)] = &[(0.5, 0.0), (1.0, 0.5), (0.5, 1.0), (0.0, 0.5)];
+const PARALLELOGRAM: &[(f64, f64)] = &[(0.2, 0.0), (1.0, 0.0), (0.8, 1.0), (0.0, 1.0)];
+const HEXAGON: &[(f64, f64)] = &[
+    (0.2, 0.0),
+    (0.8, 0.0),
+    (1.0, 0.5),
+    (0.8, 1.0),
+    (0.2, 1.0),
+    (0.0, 0.5),
+];
+
+impl NodeShape {
+    // Parse from string slice, including the Graphviz aliases
+    pub fn from_slice(s: &str) -> Option<Self> {
+        match s.trim_matches('"') {
+            "box" | "rect" | "rectangle" | "square" => Some(NodeShape::Box),
+            "ellipse" | "oval" => Some(NodeShape::Ellipse),
+            "circle" => Some(NodeShape::Circle),
+            "doublecircle" => Some(NodeShape::DoubleCircle),
+            "diamond" => Some(NodeShape::Diamond),
+            "parallelogram" => Some(NodeShape::Parallelogram),
+            "hexagon" => Some(NodeShape::Hexagon),
+            "cylinder" => Some(NodeShape::Cylinder),
+            "note" => Some(NodeShape::Note),
+            "folder" => Some(NodeShape::Folder),
+            "plaintext" | "plain" | "none" => Some(NodeShape::Plaintext),
+            _ => None,
+        }
+    }
+
+    /// The shape named by a node's `shape` attribute. Nodes without one keep the
+    /// renderer's own look rather than Graphviz's default ellipse.
+    pub fn from_attributes(attributes: &Attributes) -> Option<Self> {
+        attributes.get("shape").and_then(Self::from_slice)
+    }
+
+    // Convert to string for DOT output
+    pub const fn to_str(self) -> &'static str {
+        match self {
+            NodeShape::Box => "box",
+            NodeShape::Ellipse => "ellipse",
+            NodeShape::Circle => "circle",
+            NodeShape::DoubleCircle => "doublecircle",
+            NodeShape::Diamond => "diamond",
+            NodeShape::Parallelogram => "parallelogram",
+            NodeShape::Hexagon => "hexagon",
+            NodeShape::Cylinder => "cylinder",
+            NodeShape::Note => "note",
+            NodeShape::Folder => "folder",
+            NodeShape::Plaintext => "plaintext",
+        }
+    }
+
+    /// Corners of the outline as fractions of the node box, `None` for round shapes.
+    /// Shapes that fill their box, like cylinders and notes, are outlined by the box.
+    pub const fn polygon(self) -> Option<&'static [(f64, f64)]> {
+        match self {
+            NodeShape::Diamond => Some(DIAMOND),
+            NodeShape::Parallelogram => Some(PARALLELOGRAM),
+            NodeShape::Hexagon => Some(HEXAGON),
+            NodeShape::Ellipse | NodeShape::Circle | NodeShape::DoubleCircle => None,
+            NodeShape::Box
+            | NodeShape::Cylinder
+            | NodeShape::Note
+            | NodeShape::Folder
+            | NodeShape::Plaintext => Some(BOX),
+        }
+    }
+
+    /// The outline of this shape when drawn in the box at `pos` with `size`
+    pub fn outline(self, pos: Pos2, size: Vec2) -> Shape {
+        match self {
+            NodeShape::Ellipse | NodeShape::DoubleCircle => Shape::Ellipse { pos, size },
+            NodeShape::Circle => Shape::Circle {
+                center: Pos2 {
+                    x: pos.x + size.x / 2.0,
+                    y: pos.y + size.y / 2.0,
+                },
+                radius: size.x.min(size.y) / 2.0,
+            },
+            _ => Shape::Polygon(
+                self.polygon()
+                    .unwrap_or(BOX)
+                    .iter()
+                    .map(|(fx, fy)| Pos2 {
+                        x: pos.x + size.x * fx,
+                        y: pos.y + size.y * fy,
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
@@ -1,8 +1,9 @@
+pub mod path;
 mod utils;
+pub use path::{ArrowPath, PathSegment};
 use utils::*;
 pub use utils::{Pos2, Vec2};
 
-
 use std::f64::consts::PI;
 
 const PI2: f64 = PI * 2.0;
@@ -56,13 +57,55 @@ impl ArrowOptions {
     }
 }
 
+/// A curved arrow: a quadratic curve from `start` to `end`, bent towards `control`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ArrowGeometry {
+    pub start: Pos2,
+    pub control: Pos2,
+    pub end: Pos2,
+    /// Direction from the control point to `start`, pointing away from the curve,
+    /// for an arrowhead at the start
+    pub angle_start: f64,
+    /// Direction from the control point to `end`, for the arrowhead at the end
+    pub angle_end: f64,
+    /// Direction from the center of the start to the center of the end
+    pub angle_center: f64,
+}
+
+impl ArrowGeometry {
+    /// Geometry for the given points, with the end angles taken from the control point
+    pub fn new(start: Pos2, control: Pos2, end: Pos2, angle_center: f64) -> Self {
+        ArrowGeometry {
+            start,
+            control,
+            end,
+            angle_start: get_angle(&control, &start),
+            angle_end: get_angle(&control, &end),
+            angle_center,
+        }
+    }
+
+    /// A straight arrow, with its control point halfway along
+    pub fn straight(start: Pos2, end: Pos2) -> Self {
+        let angle = get_angle(&start, &end);
+        ArrowGeometry {
+            start,
+            control: get_point_between(&start, &end, 0.5),
+            end,
+            angle_start: angle + PI,
+            angle_end: angle,
+            angle_center: angle,
+        }
+    }
+}
+
 pub fn get_box_to_box_arrow(
     start: Pos2,
     start_size: Vec2,
     end: Pos2,
     end_size: Vec2,
     options: ArrowOptions,
-) -> (Pos2, Pos2, Pos2, f64, f64, f64) {
+) -> ArrowGeometry {
     let ArrowOptions {
         bow,
         stretch,
@@ -93,12 +136,9 @@ pub fn get_box_to_box_arrow(
     ));
     let distance = get_distance(&Pos2 { x: cx0, y: cy0 }, &Pos2 { x: cx1, y: cy1 });
 
-    if distance == 0.0 {
-        let s = Pos2 { x: cx0, y: py0 };
-        let e = Pos2 { x: cx1, y: py1 };
-        let c = get_point_between(&s, &e, 0.5);
-        let ca = get_angle(&s, &e);
-        return (s, c, e, ca, ca, ca);
+    // Identical centers, or coordinates that aren't numbers, have no direction to go
+    if distance == 0.0 || !distance.is_finite() {
+        return ArrowGeometry::straight(Pos2 { x: cx0, y: py0 }, Pos2 { x: cx1, y: py1 });
     }
 
     let rot = if get_sector(angle_center, 8) % 2 == 0 {
@@ -119,8 +159,10 @@ pub fn get_box_to_box_arrow(
     let distance_between = get_distance(&di0, &di1);
 
     if !is_colliding && straights && card % 0.5 == 0.0 {
-        let mpd = get_point_between(&di0, &di1, 0.5);
-        return (di0, mpd, di1, angle_center, angle_center - PI, angle_center);
+        return ArrowGeometry {
+            angle_center,
+            ..ArrowGeometry::straight(di0, di1)
+        };
     }
 
     let overlap_effect = if is_colliding {
@@ -160,16 +202,15 @@ pub fn get_box_to_box_arrow(
 
     let (dx0, dy0) = get_delta(final_angle0.rem_euclid(PI2));
 
+    let center0 = Pos2 { x: cx0, y: cy0 };
     let ts =
-        get_ray_rounded_rectangle_intersection(cx0, cy0, dx0, dy0, px0, py0, pw0, ph0, pad_start);
-    let start_seg =
-        get_rectangle_segment_intersected_by_ray(px0, py0, pw0, ph0, cx0, cy0, dx0, dy0);
-    let [ssx0, ssy0, ssx1, ssy1] = start_seg[0][..] else {
-        todo!()
-    };
-    let smp = get_point_between(&Pos2 { x: ssx0, y: ssy0 }, &Pos2 { x: ssx1, y: ssy1 }, 0.5);
+        get_ray_rounded_rectangle_intersection(cx0, cy0, dx0, dy0, px0, py0, pw0, ph0, pad_start)
+            .first()
+            .copied()
+            .unwrap_or(center0);
+    let smp = get_rectangle_side_midpoint(px0, py0, pw0, ph0, center0, (dx0, dy0)).unwrap_or(ts);
     let start = get_point_between(
-        &ts[0],
+        &ts,
         &smp,
         if is_colliding {
             f64::max(overlap_effect, 0.15)
@@ -189,11 +230,12 @@ pub fn get_box_to_box_arrow(
     }
 
     let end = if overlap_effect >= 0.5 {
-        let ray_angle = get_angle(&Pos2 { x: cx0, y: cy0 }, &smp);
+        let ray_angle = get_angle(&center0, &smp);
         let (dx1, dy1) = get_delta(ray_angle);
-        let e_temp =
-            get_ray_rounded_rectangle_intersection(cx1, cy1, dx1, dy1, px1, py1, pw1, ph1, pad_end);
-        e_temp[0].clone()
+        get_ray_rounded_rectangle_intersection(cx1, cy1, dx1, dy1, px1, py1, pw1, ph1, pad_end)
+            .first()
+            .copied()
+            .unwrap_or(Pos2 { x: cx1, y: cy1 })
     } else {
         let dist_offset1 = modulate(dist_effect, (0.75, 1.0), (0.0, 1.0), true);
         let overlap_effect1 = if is_colliding {
@@ -214,19 +256,16 @@ pub fn get_box_to_box_arrow(
 
         let (dx1, dy1) = get_delta(final_angle1.rem_euclid(PI2));
 
-        let te = &get_ray_rounded_rectangle_intersection(
-            cx1, cy1, dx1, dy1, px1, py1, pw1, ph1, pad_end,
-        )[0];
-        let end_seg =
-            get_rectangle_segment_intersected_by_ray(px1, py1, pw1, ph1, cx1, cy1, dx1, dy1)[0];
-        let [sex0, sey0, sex1, sey1] = end_seg[..] else {
-            todo!()
-        };
-        let se0 = Pos2 { x: sex0, y: sey0 };
-        let se1 = Pos2 { x: sex1, y: sey1 };
-        let emp = get_point_between(&se0, &se1, 0.5);
+        let te =
+            get_ray_rounded_rectangle_intersection(cx1, cy1, dx1, dy1, px1, py1, pw1, ph1, pad_end)
+                .first()
+                .copied()
+                .unwrap_or(Pos2 { x: cx1, y: cy1 });
+        let emp =
+            get_rectangle_side_midpoint(px1, py1, pw1, ph1, Pos2 { x: cx1, y: cy1 }, (dx1, dy1))
+                .unwrap_or(te);
 
-        get_point_between(te, &emp, 0.25 + overlap_effect * 0.25)
+        get_point_between(&te, &emp, 0.25 + overlap_effect * 0.25)
     };
 
     let m1 = get_point_between(&start, &end, 0.5);
@@ -243,8 +282,341 @@ pub fn get_box_to_box_arrow(
         ci_a
     };
 
-    let angle_start = get_angle(&control, &start);
-    let angle_end = get_angle(&control, &end);
+    ArrowGeometry::new(start, control, end, angle_center)
+}
+
+// Midpoint of the side of a rectangle that a ray from inside it leaves through.
+// `None` for rectangles without sides, such as zero-size boxes.
+fn get_rectangle_side_midpoint(
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    origin: Pos2,
+    (dx, dy): (f64, f64),
+) -> Option<Pos2> {
+    let [x0, y0, x1, y1] =
+        *get_rectangle_segment_intersected_by_ray(x, y, w, h, origin.x, origin.y, dx, dy)
+            .first()?;
+    Some(get_point_between(
+        &Pos2 { x: x0, y: y0 },
+        &Pos2 { x: x1, y: y1 },
+        0.5,
+    ))
+}
+
+/// Outline of one end of an arrow
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    /// Rectangle with its top-left corner at `pos` and corners rounded by `radius`
+    Rect {
+        pos: Pos2,
+        size: Vec2,
+        radius: f64,
+    },
+    /// Ellipse inscribed in the box with its top-left corner at `pos`
+    Ellipse {
+        pos: Pos2,
+        size: Vec2,
+    },
+    Circle {
+        center: Pos2,
+        radius: f64,
+    },
+    /// Convex polygon, corners in drawing order
+    Polygon(Vec<Pos2>),
+}
+
+impl Shape {
+    /// Bounding box as its top-left corner and size
+    pub fn bounds(&self) -> (Pos2, Vec2) {
+        match self {
+            Shape::Rect { pos, size, .. } | Shape::Ellipse { pos, size } => (*pos, *size),
+            Shape::Circle { center, radius } => (
+                Pos2 {
+                    x: center.x - radius,
+                    y: center.y - radius,
+                },
+                Vec2 {
+                    x: radius * 2.0,
+                    y: radius * 2.0,
+                },
+            ),
+            Shape::Polygon(corners) if corners.is_empty() => {
+                (Pos2 { x: 0.0, y: 0.0 }, Vec2 { x: 0.0, y: 0.0 })
+            }
+            Shape::Polygon(corners) => {
+                let (min_x, min_y, max_x, max_y) = corners.iter().fold(
+                    (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+                    |(min_x, min_y, max_x, max_y), p| {
+                        (
+                            min_x.min(p.x),
+                            min_y.min(p.y),
+                            max_x.max(p.x),
+                            max_y.max(p.y),
+                        )
+                    },
+                );
+                (
+                    Pos2 { x: min_x, y: min_y },
+                    Vec2 {
+                        x: max_x - min_x,
+                        y: max_y - min_y,
+                    },
+                )
+            }
+        }
+    }
+
+    /// A point inside the shape that rays to the outline start from
+    pub fn center(&self) -> Pos2 {
+        match self {
+            Shape::Circle { center, .. } => *center,
+            // The corner average is inside any convex polygon
+            Shape::Polygon(corners) if !corners.is_empty() => {
+                let n = corners.len() as f64;
+                Pos2 {
+                    x: corners.iter().map(|p| p.x).sum::<f64>() / n,
+                    y: corners.iter().map(|p| p.y).sum::<f64>() / n,
+                }
+            }
+            _ => {
+                let (pos, size) = self.bounds();
+                Pos2 {
+                    x: pos.x + size.x / 2.0,
+                    y: pos.y + size.y / 2.0,
+                }
+            }
+        }
+    }
+
+    /// Where a ray from the center towards `toward` crosses the outline.
+    /// Returns `toward` itself when there is no crossing.
+    pub fn boundary_point(&self, toward: Pos2) -> Pos2 {
+        let c = self.center();
+        let dx = toward.x - c.x;
+        let dy = toward.y - c.y;
+        let length = dx.hypot(dy);
+        if length == 0.0 {
+            return toward;
+        }
+        // A segment from the center longer than the bounds leaves the (convex) shape,
+        // crossing the outline exactly once
+        let (_, size) = self.bounds();
+        let reach = (size.x + size.y + 1.0) / length;
+        let x1 = c.x + dx * reach;
+        let y1 = c.y + dy * reach;
+        let first = |points: Vec<[f64; 2]>| points.first().map(|&[x, y]| Pos2 { x, y });
+
+        let hit = match self {
+            Shape::Rect { pos, size, radius }
+                if radius.min(size.x / 2.0).min(size.y / 2.0) > 0.0 =>
+            {
+                let r = radius.min(size.x / 2.0).min(size.y / 2.0);
+                get_ray_rounded_rectangle_intersection(
+                    c.x, c.y, dx, dy, pos.x, pos.y, size.x, size.y, r,
+                )
+                .first()
+                .copied()
+            }
+            Shape::Rect { pos, size, .. } => first(get_segment_rectangle_intersection_points(
+                c.x, c.y, x1, y1, pos.x, pos.y, size.x, size.y,
+            )),
+            Shape::Ellipse { size, .. } => {
+                let rx = size.x / 2.0;
+                let ry = size.y / 2.0;
+                if rx <= 0.0 || ry <= 0.0 {
+                    return toward;
+                }
+                // Stretch vertically so the ellipse becomes a circle of radius rx
+                let scale = rx / ry;
+                get_segment_circle_intersections(
+                    c.x,
+                    c.y,
+                    rx,
+                    c.x,
+                    c.y,
+                    x1,
+                    c.y + dy * reach * scale,
+                )
+                .and_then(first)
+                .map(|p| Pos2 {
+                    x: p.x,
+                    y: c.y + (p.y - c.y) / scale,
+                })
+            }
+            Shape::Circle { center, radius } => {
+                get_segment_circle_intersections(center.x, center.y, *radius, c.x, c.y, x1, y1)
+                    .and_then(first)
+            }
+            Shape::Polygon(corners) => corners
+                .iter()
+                .zip(corners.iter().cycle().skip(1))
+                .find_map(|(a, b)| {
+                    get_segment_segment_intersection(a.x, a.y, b.x, b.y, c.x, c.y, x1, y1)
+                })
+                .map(|[x, y]| Pos2 { x, y }),
+        };
+        hit.unwrap_or(toward)
+    }
+}
+
+/// Like [`get_box_to_box_arrow`], but the arrow starts and ends on the real outlines
+/// of `start` and `end` instead of their bounding boxes. The padding options move the
+/// ends away from the outline.
+pub fn get_shape_to_shape_arrow(
+    start: &Shape,
+    end: &Shape,
+    options: ArrowOptions,
+) -> ArrowGeometry {
+    let ArrowOptions {
+        pad_start, pad_end, ..
+    } = options;
+    let (start_pos, start_size) = start.bounds();
+    let (end_pos, end_size) = end.bounds();
+
+    // The curve is planned between the bounding boxes, padding is applied to the outlines
+    let boxed = get_box_to_box_arrow(
+        start_pos,
+        start_size,
+        end_pos,
+        end_size,
+        ArrowOptions {
+            pad_start: 0.0,
+            pad_end: 0.0,
+            ..options
+        },
+    );
+
+    let start_point = pad_outward(start, start.boundary_point(boxed.start), pad_start);
+    let end_point = pad_outward(end, end.boundary_point(boxed.end), pad_end);
+
+    ArrowGeometry::new(start_point, boxed.control, end_point, boxed.angle_center)
+}
+
+// Move a point on the outline further away from the shape's center
+fn pad_outward(shape: &Shape, point: Pos2, pad: f64) -> Pos2 {
+    if pad == 0.0 {
+        return point;
+    }
+    project_point(point, get_angle(&shape.center(), &point), pad)
+}
+
+/// Arrow between two points, the `getArrow` half of perfect-arrows.
+/// Padding moves the ends towards each other.
+pub fn get_arrow(start: Pos2, end: Pos2, options: ArrowOptions) -> ArrowGeometry {
+    let ArrowOptions {
+        bow,
+        stretch,
+        stretch_min,
+        stretch_max,
+        pad_start,
+        pad_end,
+        flip,
+        straights,
+    } = options;
+
+    let angle = get_angle(&start, &end);
+    let dist = get_distance(&start, &end);
+    let angliness = get_angliness(start, end);
+
+    // Too short, nothing to bow, or a straight/45 degree line that should stay straight
+    if dist < (pad_start + pad_end) * 2.0
+        || (bow == 0.0 && stretch == 0.0)
+        || (straights && (angliness == 0.0 || angliness == 1.0 || angliness.is_infinite()))
+    {
+        let ps = (dist - pad_start).min(pad_start).max(0.0);
+        let pe = (dist - ps).min(pad_end).max(0.0);
+        let s = project_point(start, angle, ps);
+        let e = project_point(end, angle + PI, pe);
+        return ArrowGeometry {
+            angle_start: angle + PI,
+            angle_end: angle,
+            angle_center: angle,
+            ..ArrowGeometry::straight(s, e)
+        };
+    }
+
+    let rot = if get_sector(angle, 8) % 2 == 0 { 1 } else { -1 } * if flip { -1 } else { 1 };
+    let arc = bow + modulate(dist, (stretch_min, stretch_max), (1.0, 0.0), true) * stretch;
+
+    // Control point for the unpadded ends
+    let m = get_point_between(&start, &end, 0.5);
+    let c = rotate_point(
+        &get_point_between(&start, &end, 0.5 - arc),
+        &m,
+        (PI / 2.0) * rot as f64,
+    );
+
+    // Padded ends, moved towards the control point
+    let s = project_point(start, get_angle(&start, &c), pad_start);
+    let e = project_point(end, get_angle(&end, &c), pad_end);
+
+    // Control point for the padded ends, averaged with the first one
+    let m1 = get_point_between(&s, &e, 0.5);
+    let c1 = rotate_point(
+        &get_point_between(&s, &e, 0.5 - arc),
+        &m1,
+        (PI / 2.0) * rot as f64,
+    );
+    let control = get_point_between(&c, &c1, 0.5);
+
+    ArrowGeometry::new(s, control, e, angle)
+}
+
+/// Arrow from a point to a box with its top-left corner at `end`, such as a dangling
+/// edge following the cursor. The arrow bows like [`get_arrow`] towards the box center
+/// and stops at the box edge, which `pad_end` grows and rounds as in
+/// [`get_box_to_box_arrow`]. A point inside the box gets an arrow to the center.
+pub fn get_point_to_box_arrow(
+    start: Pos2,
+    end: Pos2,
+    end_size: Vec2,
+    options: ArrowOptions,
+) -> ArrowGeometry {
+    let pad_end = options.pad_end;
+    let px = end.x - pad_end;
+    let py = end.y - pad_end;
+    let pw = end_size.x + pad_end * 2.0;
+    let ph = end_size.y + pad_end * 2.0;
+    let center = Pos2 {
+        x: end.x + end_size.x / 2.0,
+        y: end.y + end_size.y / 2.0,
+    };
+
+    let inside = start.x >= px && start.x <= px + pw && start.y >= py && start.y <= py + ph;
+    if inside {
+        return get_arrow(start, center, options);
+    }
+
+    let arrow = get_arrow(
+        start,
+        center,
+        ArrowOptions {
+            pad_end: 0.0,
+            ..options
+        },
+    );
+
+    // Where the line from the control point to the center enters the box
+    let e = get_ray_rounded_rectangle_intersection(
+        center.x,
+        center.y,
+        arrow.control.x - center.x,
+        arrow.control.y - center.y,
+        px,
+        py,
+        pw,
+        ph,
+        pad_end,
+    )
+    .first()
+    .copied()
+    .unwrap_or(center);
 
-    (start, control, end, angle_end, angle_start, angle_center)
+    ArrowGeometry {
+        end: e,
+        angle_end: get_angle(&arrow.control, &e),
+        ..arrow
+    }
 }
@@ -0,0 +1,490 @@
+//! Arrow paths made of line, quadratic, cubic and elliptical arc segments.
+use super::utils::*;
+use super::ArrowGeometry;
+use std::f64::consts::PI;
+
+/// Steps used to approximate curves for lengths and polylines
+const CURVE_STEPS: usize = 32;
+
+/// One piece of an [`ArrowPath`], starting where the previous one ended
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathSegment {
+    Line {
+        to: Pos2,
+    },
+    Quadratic {
+        control: Pos2,
+        to: Pos2,
+    },
+    Cubic {
+        control1: Pos2,
+        control2: Pos2,
+        to: Pos2,
+    },
+    /// Elliptical arc with the parameters of the SVG `A` command
+    Arc {
+        radii: Vec2,
+        /// Rotation of the ellipse's x-axis, in radians
+        rotation: f64,
+        large_arc: bool,
+        sweep: bool,
+        to: Pos2,
+    },
+}
+
+impl PathSegment {
+    /// Where the segment ends
+    pub fn end(&self) -> Pos2 {
+        match self {
+            PathSegment::Line { to }
+            | PathSegment::Quadratic { to, .. }
+            | PathSegment::Cubic { to, .. }
+            | PathSegment::Arc { to, .. } => *to,
+        }
+    }
+
+    /// Point at `t` in `0.0..=1.0` along the segment starting at `from`
+    pub fn point_at(&self, from: Pos2, t: f64) -> Pos2 {
+        match self {
+            PathSegment::Line { to } => get_point_between(&from, to, t),
+            PathSegment::Quadratic { control, to } => {
+                let mt = 1.0 - t;
+                Pos2 {
+                    x: mt * mt * from.x + 2.0 * mt * t * control.x + t * t * to.x,
+                    y: mt * mt * from.y + 2.0 * mt * t * control.y + t * t * to.y,
+                }
+            }
+            PathSegment::Cubic {
+                control1,
+                control2,
+                to,
+            } => {
+                let mt = 1.0 - t;
+                let (a, b, c, d) = (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+                Pos2 {
+                    x: a * from.x + b * control1.x + c * control2.x + d * to.x,
+                    y: a * from.y + b * control1.y + c * control2.y + d * to.y,
+                }
+            }
+            PathSegment::Arc { to, .. } => match self.ellipse(from) {
+                Some(arc) => arc.point_at(t),
+                None => get_point_between(&from, to, t),
+            },
+        }
+    }
+
+    /// Direction of travel at `t`, not normalized
+    pub fn tangent_at(&self, from: Pos2, t: f64) -> Vec2 {
+        match self {
+            PathSegment::Line { to } => Vec2 {
+                x: to.x - from.x,
+                y: to.y - from.y,
+            },
+            PathSegment::Quadratic { control, to } => {
+                let mt = 1.0 - t;
+                Vec2 {
+                    x: 2.0 * (mt * (control.x - from.x) + t * (to.x - control.x)),
+                    y: 2.0 * (mt * (control.y - from.y) + t * (to.y - control.y)),
+                }
+            }
+            PathSegment::Cubic {
+                control1,
+                control2,
+                to,
+            } => {
+                let mt = 1.0 - t;
+                let (a, b, c) = (3.0 * mt * mt, 6.0 * mt * t, 3.0 * t * t);
+                Vec2 {
+                    x: a * (control1.x - from.x)
+                        + b * (control2.x - control1.x)
+                        + c * (to.x - control2.x),
+                    y: a * (control1.y - from.y)
+                        + b * (control2.y - control1.y)
+                        + c * (to.y - control2.y),
+                }
+            }
+            PathSegment::Arc { to, .. } => match self.ellipse(from) {
+                Some(arc) => arc.tangent_at(t),
+                None => Vec2 {
+                    x: to.x - from.x,
+                    y: to.y - from.y,
+                },
+            },
+        }
+    }
+
+    /// Length of the segment, approximated for curves
+    pub fn length(&self, from: Pos2) -> f64 {
+        match self {
+            PathSegment::Line { to } => get_distance(&from, to),
+            _ => self
+                .flatten(from, CURVE_STEPS)
+                .windows(2)
+                .map(|pair| get_distance(&pair[0], &pair[1]))
+                .sum(),
+        }
+    }
+
+    /// Points along the segment after `from`, `steps` of them for curves
+    pub fn flatten(&self, from: Pos2, steps: usize) -> Vec<Pos2> {
+        match self {
+            PathSegment::Line { to } => vec![from, *to],
+            _ => {
+                let steps = steps.max(1);
+                (0..=steps)
+                    .map(|i| self.point_at(from, i as f64 / steps as f64))
+                    .collect()
+            }
+        }
+    }
+
+    // Points where the segment reaches its furthest extent along x or y, ends included
+    fn extremes(&self, from: Pos2) -> Vec<Pos2> {
+        let ts = match self {
+            PathSegment::Line { .. } => vec![],
+            PathSegment::Quadratic { control, to } => {
+                [(from.x, control.x, to.x), (from.y, control.y, to.y)]
+                    .into_iter()
+                    .filter_map(|(p0, p1, p2)| {
+                        let denom = p0 - 2.0 * p1 + p2;
+                        (denom != 0.0).then(|| (p0 - p1) / denom)
+                    })
+                    .collect()
+            }
+            PathSegment::Cubic {
+                control1,
+                control2,
+                to,
+            } => [
+                (from.x, control1.x, control2.x, to.x),
+                (from.y, control1.y, control2.y, to.y),
+            ]
+            .into_iter()
+            .flat_map(|(p0, p1, p2, p3)| cubic_extremes(p0, p1, p2, p3))
+            .collect(),
+            // Arcs are sampled rather than solved
+            PathSegment::Arc { .. } => (1..CURVE_STEPS)
+                .map(|i| i as f64 / CURVE_STEPS as f64)
+                .collect(),
+        };
+        ts.into_iter()
+            .filter(|t| (0.0..=1.0).contains(t))
+            .map(|t| self.point_at(from, t))
+            .chain([from, self.end()])
+            .collect()
+    }
+
+    // The ellipse an arc lies on, `None` when the arc is drawn as a line
+    fn ellipse(&self, from: Pos2) -> Option<ArcEllipse> {
+        let PathSegment::Arc {
+            radii,
+            rotation,
+            large_arc,
+            sweep,
+            to,
+        } = self
+        else {
+            return None;
+        };
+        ArcEllipse::new(from, *radii, *rotation, *large_arc, *sweep, *to)
+    }
+}
+
+// Parameters t where one axis of a cubic Bézier has zero slope
+fn cubic_extremes(p0: f64, p1: f64, p2: f64, p3: f64) -> Vec<f64> {
+    // Derivative is a*t^2 + b*t + c
+    let a = 3.0 * (-p0 + 3.0 * p1 - 3.0 * p2 + p3);
+    let b = 6.0 * (p0 - 2.0 * p1 + p2);
+    let c = 3.0 * (p1 - p0);
+    if a.abs() < 1e-12 {
+        return if b != 0.0 { vec![-c / b] } else { vec![] };
+    }
+    let d = b * b - 4.0 * a * c;
+    if d < 0.0 {
+        return vec![];
+    }
+    let root = d.sqrt();
+    vec![(-b + root) / (2.0 * a), (-b - root) / (2.0 * a)]
+}
+
+// An arc in center form, see the SVG spec's "elliptical arc implementation notes"
+struct ArcEllipse {
+    center: Pos2,
+    rx: f64,
+    ry: f64,
+    rotation: f64,
+    start_angle: f64,
+    sweep_angle: f64,
+}
+
+impl ArcEllipse {
+    fn new(
+        from: Pos2,
+        radii: Vec2,
+        rotation: f64,
+        large_arc: bool,
+        sweep: bool,
+        to: Pos2,
+    ) -> Option<Self> {
+        let (mut rx, mut ry) = (radii.x.abs(), radii.y.abs());
+        if rx == 0.0 || ry == 0.0 || from == to {
+            return None;
+        }
+        let (sin, cos) = rotation.sin_cos();
+        let dx = (from.x - to.x) / 2.0;
+        let dy = (from.y - to.y) / 2.0;
+        let x1 = cos * dx + sin * dy;
+        let y1 = -sin * dx + cos * dy;
+
+        // Radii too small to reach are scaled up until they just do
+        let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+        if lambda > 1.0 {
+            rx *= lambda.sqrt();
+            ry *= lambda.sqrt();
+        }
+
+        let num = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+        let den = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+        let sign = if large_arc == sweep { -1.0 } else { 1.0 };
+        let coef = sign * (num / den).max(0.0).sqrt();
+        let cx1 = coef * rx * y1 / ry;
+        let cy1 = -coef * ry * x1 / rx;
+
+        let center = Pos2 {
+            x: cos * cx1 - sin * cy1 + (from.x + to.x) / 2.0,
+            y: sin * cx1 + cos * cy1 + (from.y + to.y) / 2.0,
+        };
+        let start_angle = ((y1 - cy1) / ry).atan2((x1 - cx1) / rx);
+        let end_angle = ((-y1 - cy1) / ry).atan2((-x1 - cx1) / rx);
+        let mut sweep_angle = end_angle - start_angle;
+        if sweep && sweep_angle < 0.0 {
+            sweep_angle += 2.0 * PI;
+        } else if !sweep && sweep_angle > 0.0 {
+            sweep_angle -= 2.0 * PI;
+        }
+
+        Some(ArcEllipse {
+            center,
+            rx,
+            ry,
+            rotation,
+            start_angle,
+            sweep_angle,
+        })
+    }
+
+    fn point_at(&self, t: f64) -> Pos2 {
+        let (sin, cos) = self.rotation.sin_cos();
+        let (sin_a, cos_a) = (self.start_angle + self.sweep_angle * t).sin_cos();
+        Pos2 {
+            x: self.center.x + self.rx * cos * cos_a - self.ry * sin * sin_a,
+            y: self.center.y + self.rx * sin * cos_a + self.ry * cos * sin_a,
+        }
+    }
+
+    fn tangent_at(&self, t: f64) -> Vec2 {
+        let (sin, cos) = self.rotation.sin_cos();
+        let (sin_a, cos_a) = (self.start_angle + self.sweep_angle * t).sin_cos();
+        Vec2 {
+            x: (-self.rx * cos * sin_a - self.ry * sin * cos_a) * self.sweep_angle,
+            y: (-self.rx * sin * sin_a + self.ry * cos * cos_a) * self.sweep_angle,
+        }
+    }
+}
+
+/// The path an edge is drawn along: a start point and the segments that follow it
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArrowPath {
+    pub start: Pos2,
+    pub segments: Vec<PathSegment>,
+}
+
+impl From<&ArrowGeometry> for ArrowPath {
+    /// The single quadratic curve of an arrow
+    fn from(arrow: &ArrowGeometry) -> Self {
+        ArrowPath {
+            start: arrow.start,
+            segments: vec![PathSegment::Quadratic {
+                control: arrow.control,
+                to: arrow.end,
+            }],
+        }
+    }
+}
+
+impl ArrowPath {
+    /// Straight lines through `points`, each corner rounded off with a curve
+    /// of up to `radius` so the path turns smoothly
+    pub fn rounded_polyline(points: &[Pos2], radius: f64) -> Self {
+        let mut points = points.to_vec();
+        points.dedup();
+        let start = points.first().copied().unwrap_or(Pos2 { x: 0.0, y: 0.0 });
+        let mut segments = Vec::new();
+        for corner in points.windows(3) {
+            let (before, at, after) = (corner[0], corner[1], corner[2]);
+            let into = get_distance(&before, &at);
+            let out = get_distance(&at, &after);
+            let r = radius.min(into / 2.0).min(out / 2.0).max(0.0);
+            segments.push(PathSegment::Line {
+                to: Pos2 {
+                    x: at.x + (before.x - at.x) / into * r,
+                    y: at.y + (before.y - at.y) / into * r,
+                },
+            });
+            segments.push(PathSegment::Quadratic {
+                control: at,
+                to: Pos2 {
+                    x: at.x + (after.x - at.x) / out * r,
+                    y: at.y + (after.y - at.y) / out * r,
+                },
+            });
+        }
+        if points.len() > 1 {
+            segments.push(PathSegment::Line {
+                to: points[points.len() - 1],
+            });
+        }
+        ArrowPath { start, segments }
+    }
+
+    /// Where the path ends
+    pub fn end(&self) -> Pos2 {
+        self.segments
+            .last()
+            .map(PathSegment::end)
+            .unwrap_or(self.start)
+    }
+
+    // Each segment with the point it starts from
+    fn with_starts(&self) -> impl Iterator<Item = (Pos2, &PathSegment)> {
+        let ends = self.segments.iter().map(PathSegment::end);
+        std::iter::once(self.start).chain(ends).zip(&self.segments)
+    }
+
+    /// Total length, approximated for curves
+    pub fn length(&self) -> f64 {
+        self.with_starts()
+            .map(|(from, segment)| segment.length(from))
+            .sum()
+    }
+
+    // The segment at `t` of the whole path, with its start and the local t.
+    // Segments get a share of `0.0..=1.0` proportional to their length.
+    fn locate(&self, t: f64) -> Option<(Pos2, &PathSegment, f64)> {
+        let lengths: Vec<f64> = self
+            .with_starts()
+            .map(|(from, segment)| segment.length(from))
+            .collect();
+        let total: f64 = lengths.iter().sum();
+        let mut remaining = t.clamp(0.0, 1.0) * total;
+
+        let last = self.segments.len().checked_sub(1)?;
+        for (i, ((from, segment), length)) in self.with_starts().zip(&lengths).enumerate() {
+            if remaining <= *length || i == last {
+                let local = if *length > 0.0 {
+                    (remaining / length).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                return Some((from, segment, local));
+            }
+            remaining -= length;
+        }
+        None
+    }
+
+    /// Point at `t` in `0.0..=1.0`, by distance along the path
+    pub fn point_at(&self, t: f64) -> Pos2 {
+        self.locate(t)
+            .map(|(from, segment, t)| segment.point_at(from, t))
+            .unwrap_or(self.start)
+    }
+
+    /// Direction of travel at `t` in `0.0..=1.0`, not normalized
+    pub fn tangent_at(&self, t: f64) -> Vec2 {
+        self.locate(t)
+            .map(|(from, segment, t)| segment.tangent_at(from, t))
+            .unwrap_or(Vec2 { x: 0.0, y: 0.0 })
+    }
+
+    /// Angle in radians of the direction of travel at `t`
+    pub fn angle_at(&self, t: f64) -> f64 {
+        let Vec2 { x, y } = self.tangent_at(t);
+        y.atan2(x)
+    }
+
+    /// Tight bounding box as its top-left corner and size
+    pub fn bounding_box(&self) -> (Pos2, Vec2) {
+        let points: Vec<Pos2> = self
+            .with_starts()
+            .flat_map(|(from, segment)| segment.extremes(from))
+            .chain([self.start])
+            .collect();
+        let (min_x, min_y, max_x, max_y) = points.iter().fold(
+            (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+            |(min_x, min_y, max_x, max_y), p| {
+                (
+                    min_x.min(p.x),
+                    min_y.min(p.y),
+                    max_x.max(p.x),
+                    max_y.max(p.y),
+                )
+            },
+        );
+        (
+            Pos2 { x: min_x, y: min_y },
+            Vec2 {
+                x: max_x - min_x,
+                y: max_y - min_y,
+            },
+        )
+    }
+
+    /// The path as a polyline, curves split into `steps` pieces each
+    pub fn flatten(&self, steps: usize) -> Vec<Pos2> {
+        let mut points = vec![self.start];
+        for (from, segment) in self.with_starts() {
+            points.extend(segment.flatten(from, steps).into_iter().skip(1));
+        }
+        points
+    }
+
+    /// SVG path data for the `d` attribute
+    pub fn to_svg(&self) -> String {
+        let mut d = format!("M{},{}", self.start.x, self.start.y);
+        for segment in &self.segments {
+            let command = match segment {
+                PathSegment::Line { to } => format!(" L{},{}", to.x, to.y),
+                PathSegment::Quadratic { control, to } => {
+                    format!(" Q{},{} {},{}", control.x, control.y, to.x, to.y)
+                }
+                PathSegment::Cubic {
+                    control1,
+                    control2,
+                    to,
+                } => format!(
+                    " C{},{} {},{} {},{}",
+                    control1.x, control1.y, control2.x, control2.y, to.x, to.y
+                ),
+                PathSegment::Arc {
+                    radii,
+                    rotation,
+                    large_arc,
+                    sweep,
+                    to,
+                } => format!(
+                    " A{},{} {} {} {} {},{}",
+                    radii.x,
+                    radii.y,
+                    rotation.to_degrees(),
+                    u8::from(*large_arc),
+                    u8::from(*sweep),
+                    to.x,
+                    to.y
+                ),
+            };
+            d.push_str(&command);
+        }
+        d
+    }
+}
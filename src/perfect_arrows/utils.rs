@@ -26,6 +26,10 @@ pub struct Pos2 {
 pub fn modulate(value: f64, range_a: (f64, f64), range_b: (f64, f64), clamp: bool) -> f64 {
     let (from_low, from_high) = range_a;
     let (to_low, to_high) = range_b;
+    // An empty range maps to a step instead of dividing by zero
+    if from_high == from_low {
+        return if value < from_low { to_low } else { to_high };
+    }
     let result = to_low + ((value - from_low) / (from_high - from_low)) * (to_high - to_low);
     if clamp {
         if to_low < to_high {
@@ -365,14 +369,17 @@ pub fn get_line_between_rounded_rectangles(
     let cy0 = y0 + h0 / 2.0;
     let cx1 = x1 + w1 / 2.0;
     let cy1 = y1 + h1 / 2.0;
+    // Boxes without sides to cross are left at their centers
     let di0 =
         get_ray_rounded_rectangle_intersection(cx0, cy0, cx1 - cx0, cy1 - cy0, x0, y0, w0, h0, r0)
-            [0]
-        .clone();
+            .first()
+            .copied()
+            .unwrap_or(Pos2 { x: cx0, y: cy0 });
     let di1 =
         get_ray_rounded_rectangle_intersection(cx1, cy1, cx0 - cx1, cy0 - cy1, x1, y1, w1, h1, r1)
-            [0]
-        .clone();
+            .first()
+            .copied()
+            .unwrap_or(Pos2 { x: cx1, y: cy1 });
 
     (di0, di1)
 }
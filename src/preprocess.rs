@@ -0,0 +1,298 @@
+//! Rewrite DOT source around dot-parser limitations before parsing it.
+//!
+//! dot-parser matches the compass point `n` before `ne` (and `s` before `se`/`sw`),
+//! so `a:ne -> b` parses as a node `a:n` followed by an edge from `e`. Port names
+//! fare the same, `a:east` is read as `a:e` followed by a node `ast`.
+//! Bare ports other than one-letter compass points are quoted into the port id
+//! instead (`a:"ne"`, `a:"east"`, `a:"p:ne"`), which
+//! [`EdgePort`](crate::edge_port::EdgePort) splits again.
+//!
+//! dot-parser also rejects HTML-like strings with nested tags (`<<b>x</b>>`).
+//! The inner angle brackets are swapped for private use characters, and
+//! [`restore_html`] puts them back once the attribute value is read.
+//!
+//! Inside quoted strings dot-parser reads `\\"` as an escaped quote, while Graphviz
+//! reads a `\\` pair followed by the closing quote. Backslash pairs are swapped for
+//! a private use character, which [`restore_backslashes`] turns back into the pair.
+
+/// DOT source after rewriting, with a map back to the original byte offsets
+pub(crate) struct Preprocessed {
+    pub(crate) text: String,
+    /// Original offset of every byte of `text`, plus one for the end of input
+    offsets: Vec<usize>,
+}
+
+impl Preprocessed {
+    /// Byte offset in the original source for an offset in the rewritten text
+    pub(crate) fn original_offset(&self, offset: usize) -> usize {
+        let last = self.offsets.last().copied().unwrap_or(0);
+        self.offsets.get(offset).copied().unwrap_or(last)
+    }
+
+    /// Tokens of the rewritten text, the way dot-parser splits it, with their byte
+    /// ranges in the original source. Whitespace and comments are skipped.
+    pub(crate) fn tokens(&self) -> Vec<Token<'_>> {
+        let text = self.text.as_str();
+        let bytes = text.as_bytes();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            let next = bytes.get(i + 1).copied();
+            let end = match bytes[i] {
+                b if b.is_ascii_whitespace() => {
+                    i += 1;
+                    continue;
+                }
+                b'/' if next == Some(b'/') => {
+                    i = line_end(bytes, i);
+                    continue;
+                }
+                b'/' if next == Some(b'*') => {
+                    i = text[i + 2..]
+                        .find("*/")
+                        .map(|end| i + 2 + end + 2)
+                        .unwrap_or(bytes.len());
+                    continue;
+                }
+                b'#' if at_line_start(bytes, i) => {
+                    i = line_end(bytes, i);
+                    continue;
+                }
+                b'"' => quoted_end(bytes, i),
+                b'<' => html_end(bytes, i),
+                b'-' if matches!(next, Some(b'>' | b'-')) => i + 2,
+                b'{' | b'}' | b'[' | b']' | b';' | b',' | b'=' | b':' => i + 1,
+                // Identifiers and numerals, a numeral may start with a minus
+                _ => {
+                    let start = if bytes[i] == b'-' { i + 1 } else { i };
+                    let end = start
+                        + text[start..]
+                            .find(|c: char| !is_id_char(c))
+                            .unwrap_or(text.len() - start);
+                    if end > start {
+                        end
+                    } else {
+                        i + text[i..].chars().next().map_or(1, char::len_utf8)
+                    }
+                }
+            };
+            tokens.push(Token {
+                text: &text[i..end],
+                start: self.original_offset(i),
+                end: self.original_offset(end - 1) + 1,
+            });
+            i = end;
+        }
+        tokens
+    }
+}
+
+/// A token of the rewritten text
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Token<'a> {
+    /// The token as dot-parser reads it
+    pub(crate) text: &'a str,
+    /// Byte range in the original source
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+impl Token<'_> {
+    /// Whether this is one of the DOT keywords, which can't be unquoted ids
+    pub(crate) fn is_keyword(&self) -> bool {
+        matches!(
+            self.text,
+            "node" | "edge" | "graph" | "digraph" | "subgraph" | "strict"
+        )
+    }
+
+    /// Whether this is an id: a name, numeral, quoted or HTML-like string
+    pub(crate) fn is_id(&self) -> bool {
+        !self.is_keyword()
+            && self
+                .text
+                .starts_with(|c: char| c == '"' || c == '<' || c == '-' || is_id_char(c))
+            && !matches!(self.text, "->" | "--")
+    }
+}
+
+/// Compass points dot-parser reads without help
+const ONE_LETTER_COMPASS: [&str; 6] = ["n", "e", "s", "w", "c", "_"];
+const TWO_LETTER_COMPASS: [&str; 4] = ["ne", "nw", "se", "sw"];
+
+/// Stand-ins for `<` and `>` nested inside HTML-like strings
+const HTML_OPEN: char = '\u{E000}';
+const HTML_CLOSE: char = '\u{E001}';
+
+/// Stand-in for a `\\` pair inside a quoted string
+const BACKSLASH_PAIR: char = '\u{E002}';
+
+/// Undo the backslash pair rewrite in a quoted string
+pub(crate) fn restore_backslashes(value: &str) -> String {
+    value.replace(BACKSLASH_PAIR, "\\\\")
+}
+
+/// Undo the nested angle bracket rewrite in an HTML-like attribute value
+pub(crate) fn restore_html(value: &str) -> String {
+    value.replace(HTML_OPEN, "<").replace(HTML_CLOSE, ">")
+}
+
+/// Apply all rewrites to `source`
+pub(crate) fn preprocess(source: &str) -> Preprocessed {
+    let bytes = source.as_bytes();
+    let mut rewrites = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        i = match bytes[i] {
+            b'"' => {
+                let end = quoted_end(bytes, i);
+                let mut j = i + 1;
+                while j < end {
+                    if bytes[j] == b'\\' {
+                        if bytes.get(j + 1) == Some(&b'\\') {
+                            rewrites.push((j, j + 2, BACKSLASH_PAIR.to_string()));
+                        }
+                        j += 2;
+                    } else {
+                        j += 1;
+                    }
+                }
+                end
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => line_end(bytes, i),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => source[i + 2..]
+                .find("*/")
+                .map(|end| i + 2 + end + 2)
+                .unwrap_or(bytes.len()),
+            b'#' if at_line_start(bytes, i) => line_end(bytes, i),
+            b'<' => {
+                let end = html_end(bytes, i);
+                // Everything but the outer brackets
+                for (j, &b) in bytes.iter().enumerate().take(end - 1).skip(i + 1) {
+                    match b {
+                        b'<' => rewrites.push((j, j + 1, HTML_OPEN.to_string())),
+                        b'>' => rewrites.push((j, j + 1, HTML_CLOSE.to_string())),
+                        _ => {}
+                    }
+                }
+                end
+            }
+            b':' => match compass_port(source, i) {
+                Some((end, replacement)) => {
+                    rewrites.push((i, end, replacement));
+                    end
+                }
+                None => i + 1,
+            },
+            _ => i + 1,
+        };
+    }
+    apply(source, rewrites)
+}
+
+// Replace each `start..end` range, recording where every output byte came from
+fn apply(source: &str, rewrites: Vec<(usize, usize, String)>) -> Preprocessed {
+    let mut text = String::with_capacity(source.len());
+    let mut offsets = Vec::with_capacity(source.len() + 1);
+    let mut copied = 0;
+    for (start, end, replacement) in rewrites {
+        text.push_str(&source[copied..start]);
+        offsets.extend(copied..start);
+        text.push_str(&replacement);
+        offsets.extend((0..replacement.len()).map(|k| (start + k).min(end - 1)));
+        copied = end;
+    }
+    text.push_str(&source[copied..]);
+    offsets.extend(copied..=source.len());
+    Preprocessed { text, offsets }
+}
+
+// `:ne` becomes `:"ne"`, `:east` becomes `:"east"` and `:port:ne` becomes `:"port:ne"`.
+// Quoted ports and one-letter compass points stay as they are.
+fn compass_port(source: &str, colon: usize) -> Option<(usize, String)> {
+    let (port_end, port) = id_token(source, colon + 1)?;
+    let is_compass = |token: &str, end: usize| {
+        (ONE_LETTER_COMPASS.contains(&token) || TWO_LETTER_COMPASS.contains(&token))
+            && !source[end..].starts_with(is_id_char)
+    };
+
+    let compass = source[port_end..]
+        .strip_prefix(':')
+        .and_then(|rest| id_token(source, source.len() - rest.len()))
+        .filter(|&(end, compass)| is_compass(compass, end));
+    if let Some((compass_end, compass)) = compass {
+        let port = port
+            .strip_prefix('"')
+            .and_then(|p| p.strip_suffix('"'))
+            .unwrap_or(port);
+        return Some((compass_end, format!(":\"{port}:{compass}\"")));
+    }
+
+    let is_bare = !port.starts_with('"');
+    (is_bare && !ONE_LETTER_COMPASS.contains(&port)).then(|| (port_end, format!(":\"{port}\"")))
+}
+
+// A quoted string or a bare identifier starting at `start`
+fn id_token(source: &str, start: usize) -> Option<(usize, &str)> {
+    let end = if source[start..].starts_with('"') {
+        quoted_end(source.as_bytes(), start)
+    } else {
+        start
+            + source[start..]
+                .find(|c: char| !is_id_char(c))
+                .unwrap_or(source.len() - start)
+    };
+    (end > start).then(|| (end, &source[start..end]))
+}
+
+fn is_id_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
+
+// End of the quoted string starting at `start`, past the closing quote
+fn quoted_end(bytes: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+// End of the HTML-like string starting at `start`, past the matching `>`
+fn html_end(bytes: &[u8], start: usize) -> usize {
+    let mut depth = 0;
+    for (i, &b) in bytes.iter().enumerate().skip(start) {
+        match b {
+            b'<' => depth += 1,
+            b'>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    bytes.len()
+}
+
+fn line_end(bytes: &[u8], start: usize) -> usize {
+    bytes[start..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map(|end| start + end)
+        .unwrap_or(bytes.len())
+}
+
+// `#` lines are preprocessor output and only count as comments at the start of a line
+fn at_line_start(bytes: &[u8], i: usize) -> bool {
+    bytes[..i]
+        .iter()
+        .rev()
+        .take_while(|&&b| b != b'\n')
+        .all(|b| b.is_ascii_whitespace())
+}
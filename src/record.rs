@@ -0,0 +1,104 @@
+//! Graphviz record labels (`shape=record` / `Mrecord`), e.g. `"{<f0> Seqno|<f1> Prev}"`.
+use crate::attributes::Attributes;
+
+/// One field of a record label
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecordField {
+    /// A text field, optionally named by a port (`<f0> Seqno`)
+    Text { port: Option<String>, text: String },
+    /// A `{...}` group, its fields are laid out in the other direction
+    Group(Vec<RecordField>),
+}
+
+impl RecordField {
+    /// Port names of this field and all fields nested in it
+    pub fn ports(&self) -> Vec<&str> {
+        match self {
+            RecordField::Text { port, .. } => port.as_deref().into_iter().collect(),
+            RecordField::Group(fields) => fields.iter().flat_map(RecordField::ports).collect(),
+        }
+    }
+}
+
+/// Whether the node attributes ask for a record shape
+pub fn is_record_shape(attributes: &Attributes) -> bool {
+    matches!(attributes.get("shape"), Some("record" | "Mrecord"))
+}
+
+/// Parse a record label into its top level fields.
+///
+/// `|` separates fields, `{ }` nests a group, `<port>` names a field, and `\` escapes
+/// any of those characters. `\n`, `\l` and `\r` are line breaks.
+pub fn parse_record(label: &str) -> Vec<RecordField> {
+    let mut chars = label.chars().peekable();
+    parse_fields(&mut chars, false)
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::Chars<'a>>;
+
+// Fields up to the end of the label or the `}` closing the current group
+fn parse_fields(chars: &mut Chars, nested: bool) -> Vec<RecordField> {
+    let mut fields = Vec::new();
+    loop {
+        fields.push(parse_field(chars, nested));
+        match chars.peek() {
+            Some('|') => {
+                chars.next();
+            }
+            // A stray `}` after a top level group starts the next text field
+            Some('}') if !nested => {}
+            Some('}') => {
+                chars.next();
+                return fields;
+            }
+            _ => return fields,
+        }
+    }
+}
+
+// A single field, stopping before the `|` that follows it, or the `}` closing its group.
+// Outside of any group an unmatched `}` is kept as text.
+fn parse_field(chars: &mut Chars, nested: bool) -> RecordField {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+    if chars.next_if_eq(&'{').is_some() {
+        let group = RecordField::Group(parse_fields(chars, true));
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        return group;
+    }
+
+    let mut port = None;
+    let mut text = String::new();
+    while let Some(&c) = chars.peek() {
+        match c {
+            '|' => break,
+            '}' if nested => break,
+            '\\' => {
+                chars.next();
+                match chars.next() {
+                    Some('n' | 'l' | 'r') => text.push('\n'),
+                    Some(escaped) => text.push(escaped),
+                    None => text.push('\\'),
+                }
+            }
+            '<' => {
+                chars.next();
+                let mut name = String::new();
+                while let Some(c) = chars.next_if(|&c| c != '>') {
+                    name.push(c);
+                }
+                chars.next();
+                port = Some(name.trim().to_string());
+            }
+            _ => {
+                chars.next();
+                text.push(c);
+            }
+        }
+    }
+
+    RecordField::Text {
+        port,
+        text: text.trim().to_string(),
+    }
+}
@@ -0,0 +1,208 @@
+//! Ordering, cycles, components and paths over parsed graphs
+use dioxus_plumb::algorithms::{
+    critical_path, edge_weight, find_cycle, is_acyclic, longest_path, ranks, shortest_path,
+    strongly_connected_components, topological_sort, weighted_shortest_path, Path,
+};
+use dioxus_plumb::graph_data::{parse_graph, GraphData};
+
+fn edge_ids<'a>(path: &Path<'a>) -> Vec<&'a str> {
+    path.edges.iter().map(|edge| edge.id.as_str()).collect()
+}
+
+// Each edge of `graph` goes from an earlier to a later node of `order`
+fn respects_edges(graph: &GraphData, order: &[&str]) -> bool {
+    let position = |id: &str| order.iter().position(|&node| node == id).unwrap();
+    graph
+        .edges
+        .iter()
+        .all(|edge| position(&edge.source) < position(&edge.target))
+}
+
+#[test]
+fn topological_order_follows_edges_and_document_order() {
+    let graph = parse_graph("digraph { e; d -> b; a -> b -> c; d -> c }").unwrap();
+    let order = topological_sort(&graph).unwrap();
+    assert_eq!(order, ["e", "d", "a", "b", "c"]);
+    assert!(respects_edges(&graph, &order));
+    assert!(is_acyclic(&graph));
+}
+
+#[test]
+fn topological_sort_reports_the_cycle() {
+    let graph = parse_graph("digraph { start -> a -> b -> c -> a; c -> end }").unwrap();
+    let cycle = topological_sort(&graph).unwrap_err();
+    assert_eq!(cycle.nodes, ["a", "b", "c"]);
+    assert_eq!(cycle.to_string(), "Graph has a cycle: a -> b -> c -> a");
+    assert!(ranks(&graph).is_err());
+}
+
+#[test]
+fn found_cycles_are_real_cycles() {
+    let graph = parse_graph("digraph { x -> y; y -> z; z -> w; w -> y; z -> v }").unwrap();
+    let cycle = find_cycle(&graph).unwrap();
+    assert_eq!(cycle.nodes, ["y", "z", "w"]);
+    // Every node has an edge to the next, the last one back to the first
+    let next = cycle.nodes.iter().cycle().skip(1);
+    for (from, to) in cycle.nodes.iter().zip(next) {
+        assert!(graph
+            .edges
+            .iter()
+            .any(|edge| edge.source == *from && edge.target == *to));
+    }
+
+    let self_loop = parse_graph("digraph { a -> b -> b }").unwrap();
+    assert_eq!(find_cycle(&self_loop).unwrap().nodes, ["b"]);
+    assert!(find_cycle(&parse_graph("digraph { a -> b -> c; a -> c }").unwrap()).is_none());
+}
+
+#[test]
+fn components_group_mutually_reachable_nodes() {
+    let graph =
+        parse_graph("digraph { a -> b -> c -> a; c -> d; d -> e -> d; e -> lonely2; lonely }")
+            .unwrap();
+    let mut components: Vec<Vec<&str>> = strongly_connected_components(&graph)
+        .into_iter()
+        .map(|mut component| {
+            component.sort();
+            component
+        })
+        .collect();
+
+    // Edges between components only point to later ones
+    let position = |id: &str| {
+        components
+            .iter()
+            .position(|component| component.contains(&id))
+            .unwrap()
+    };
+    assert!(position("a") < position("d"));
+    assert!(position("d") < position("lonely2"));
+
+    components.sort();
+    assert_eq!(
+        components,
+        [
+            vec!["a", "b", "c"],
+            vec!["d", "e"],
+            vec!["lonely"],
+            vec!["lonely2"]
+        ]
+    );
+}
+
+#[test]
+fn ranks_count_edges_on_the_longest_path_in() {
+    let graph = parse_graph("digraph { a -> b -> c; a -> c; d }").unwrap();
+    let ranks = ranks(&graph).unwrap();
+    assert_eq!(ranks["a"], 0);
+    assert_eq!(ranks["b"], 1);
+    assert_eq!(ranks["c"], 2);
+    assert_eq!(ranks["d"], 0);
+}
+
+const WEIGHTED: &str = r#"digraph {
+    a -> b [weight=1];
+    b -> c [weight=1];
+    c -> d [weight=1];
+    a -> d [weight=10];
+    a -> e [weight="heavy"];
+    e -> d;
+}"#;
+
+#[test]
+fn bfs_counts_edges_while_dijkstra_sums_weights() {
+    let graph = parse_graph(WEIGHTED).unwrap();
+
+    let fewest = shortest_path(&graph, "a", "d").unwrap();
+    assert_eq!(fewest.nodes, ["a", "d"]);
+    assert_eq!(edge_ids(&fewest), ["a-d"]);
+    assert_eq!(fewest.length, 1.0);
+
+    // Missing and non-numeric weights count as 1, so a -> e -> d costs 2
+    let cheapest = weighted_shortest_path(&graph, "a", "d", "weight").unwrap();
+    assert_eq!(cheapest.nodes, ["a", "e", "d"]);
+    assert_eq!(cheapest.length, 2.0);
+
+    assert!(shortest_path(&graph, "d", "a").is_none());
+    assert!(weighted_shortest_path(&graph, "missing", "a", "weight").is_none());
+}
+
+#[test]
+fn missing_and_unparsable_weights_count_as_one() {
+    let graph = parse_graph(
+        r#"digraph { a -> b [w=2.5]; a -> c; a -> d [w="x"]; a -> e [w=" 3 "]; a -> f [w=inf] }"#,
+    )
+    .unwrap();
+    let weights: Vec<f64> = graph
+        .edges
+        .iter()
+        .map(|edge| edge_weight(edge, "w"))
+        .collect();
+    assert_eq!(weights, [2.5, 1.0, 1.0, 3.0, 1.0]);
+}
+
+#[test]
+fn longest_and_critical_paths_take_the_heaviest_route() {
+    let graph = parse_graph(
+        r#"digraph {
+            design -> build [t=3];
+            design -> docs [t=1];
+            build -> test [t=4];
+            docs -> release [t=1];
+            test -> release [t=2];
+            other -> release [t=1];
+        }"#,
+    )
+    .unwrap();
+
+    let longest = longest_path(&graph, "design", "release", "t")
+        .unwrap()
+        .unwrap();
+    assert_eq!(longest.nodes, ["design", "build", "test", "release"]);
+    assert_eq!(longest.length, 9.0);
+    assert_eq!(longest.edges.len(), 3);
+
+    assert_eq!(
+        longest_path(&graph, "release", "design", "t").unwrap(),
+        None
+    );
+    assert_eq!(
+        longest_path(&graph, "missing", "design", "t").unwrap(),
+        None
+    );
+
+    let critical = critical_path(&graph, "t").unwrap().unwrap();
+    assert_eq!(critical.nodes, longest.nodes);
+    assert_eq!(critical.length, 9.0);
+
+    let empty = parse_graph("digraph {}").unwrap();
+    assert_eq!(critical_path(&empty, "t").unwrap(), None);
+
+    let cyclic = parse_graph("digraph { a -> b -> a }").unwrap();
+    assert!(critical_path(&cyclic, "t").is_err());
+    assert!(longest_path(&cyclic, "a", "b", "t").is_err());
+}
+
+#[test]
+fn subgraph_endpoints_expand_to_their_nodes() {
+    let graph = parse_graph("digraph { {a b} -> c -> {d e} }").unwrap();
+
+    let order = topological_sort(&graph).unwrap();
+    assert_eq!(order.len(), 5);
+    assert!(respects_edges(&graph, &order));
+
+    let path = shortest_path(&graph, "b", "e").unwrap();
+    assert_eq!(path.nodes, ["b", "c", "e"]);
+    assert_eq!(
+        critical_path(&graph, "weight").unwrap().unwrap().length,
+        2.0
+    );
+    assert_eq!(strongly_connected_components(&graph).len(), 5);
+}
+
+#[test]
+fn edges_inside_subgraph_endpoints_are_kept() {
+    let graph = parse_graph("digraph { subgraph { x -> y } -> z }").unwrap();
+    assert_eq!(topological_sort(&graph).unwrap(), ["x", "y", "z"]);
+    assert_eq!(ranks(&graph).unwrap()["z"], 2);
+}
@@ -0,0 +1,59 @@
+//! DOM ids of rendered graphs stay unique when several graphs share a page
+use dioxus::dioxus_core::{AttributeValue, Mutation};
+use dioxus::prelude::*;
+use dioxus_plumb::dot_renderer::{DotGraph, DotNodeRenderer};
+use dioxus_plumb::graph_data::NodeData;
+
+const DOT: &str = "digraph { subgraph cluster_a { label=A; x } y; x -> y }";
+
+// Shows the node id and nothing else
+#[derive(Clone, PartialEq)]
+struct IdRenderer;
+
+impl DotNodeRenderer for IdRenderer {
+    fn render_node(&self, node: &NodeData) -> Element {
+        rsx! { "{node.id}" }
+    }
+}
+
+fn two_graphs() -> Element {
+    rsx! {
+        DotGraph { dot: DOT, renderer: IdRenderer, class: None }
+        DotGraph { dot: DOT, renderer: IdRenderer, class: None }
+    }
+}
+
+// Values of the `name` attributes set while building the page
+fn attribute_values(app: fn() -> Element, name: &str) -> Vec<String> {
+    let mut dom = VirtualDom::new(app);
+    dom.rebuild_to_vec()
+        .edits
+        .into_iter()
+        .filter_map(|edit| match edit {
+            Mutation::SetAttribute {
+                name: set,
+                value: AttributeValue::Text(value),
+                ..
+            } if set == name => Some(value),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn graphs_sharing_a_page_get_distinct_ids() {
+    let ids = attribute_values(two_graphs, "id");
+    let mut unique = ids.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(unique.len(), ids.len(), "{ids:?}");
+    let clusters = ids.iter().filter(|id| id.ends_with("-cluster_a")).count();
+    assert_eq!(clusters, 2, "{ids:?}");
+}
+
+#[test]
+fn graph_ids_stay_raw_in_data_attributes() {
+    let mut graph_ids = attribute_values(two_graphs, "data-graph-id");
+    graph_ids.sort();
+    assert_eq!(graph_ids, ["G", "G", "cluster_a", "cluster_a"]);
+}
@@ -0,0 +1,127 @@
+//! GraphData written back to DOT parses into the same GraphData
+use dioxus_plumb::graph_data::{parse_graph, GraphData};
+
+// Parse, write and parse again, expecting nothing lost on the way
+fn round_trip(dot: &str) -> GraphData {
+    let graph = parse_graph(dot).unwrap();
+    let written = graph.to_dot();
+    let reparsed =
+        parse_graph(&written).unwrap_or_else(|err| panic!("{err}\nin written DOT:\n{written}"));
+    assert_eq!(reparsed, graph, "written DOT:\n{written}");
+    graph
+}
+
+#[test]
+fn root_graph_attributes_survive() {
+    let graph = round_trip(
+        "digraph { graph [style=filled, bgcolor=red]; rankdir=LR; label=\"Top\"; splines=ortho; a }",
+    );
+    assert_eq!(graph.style.as_deref(), Some("filled"));
+    assert_eq!(graph.attributes.get("bgcolor"), Some("red"));
+}
+
+#[test]
+fn clusters_and_nested_subgraphs_survive() {
+    round_trip(
+        r#"digraph {
+            subgraph cluster_outer {
+                label="Outer"; style=dashed;
+                a;
+                subgraph cluster_inner { color=blue; b; c [shape=box] }
+                subgraph group { d }
+            }
+            subgraph { e }
+            a -> b -> c;
+            d -> e [label="d to e"];
+        }"#,
+    );
+}
+
+#[test]
+fn defaults_survive() {
+    round_trip(
+        r#"digraph {
+            graph [fontname=Arial];
+            node [shape=box, color=gray];
+            edge [style=dashed];
+            a -> b;
+            subgraph cluster_x { node [shape=circle]; c; c -> a }
+        }"#,
+    );
+}
+
+#[test]
+fn ports_and_compass_points_survive() {
+    round_trip(
+        r#"digraph {
+            a [shape=record, label="<in> In|{<out> Out|<err> Err}"];
+            a:out -> b:w;
+            a:err:se -> b:"odd port":n;
+            b:ne -> a:in;
+        }"#,
+    );
+}
+
+#[test]
+fn html_labels_survive() {
+    round_trip(
+        r#"digraph {
+            t [shape=plain, label=<<TABLE BORDER="0"><TR><TD PORT="p">A &amp; B</TD><TD><B>bold</B><BR/>x</TD></TR></TABLE>>];
+            t:p -> u [label=<<I>edge</I>>];
+        }"#,
+    );
+}
+
+#[test]
+fn strict_and_undirected_graphs_survive() {
+    let graph = round_trip("strict graph { a -- b [dir=both]; a -- c }");
+    assert!(graph.strict);
+    round_trip("digraph { a -> b [dir=none]; a -> b; a -> b [id=third] }");
+}
+
+#[test]
+fn awkward_strings_survive() {
+    round_trip(
+        r#"digraph {
+            "node" [label="say \"hi\""];
+            "with space" [label="line one
+line two", tooltip="C:\\path\\"];
+            "back\\slash" -> "node" [label="a\lb\n"];
+        }"#,
+    );
+}
+
+#[test]
+fn backslashes_before_quotes_and_at_the_end_survive() {
+    let labels = [
+        "ends with \\",
+        "ends with two \\\\",
+        "ends with three \\\\\\",
+        "before \\\" quote",
+        "before \\\\\" quote",
+        "in \\ the \\\\ middle",
+        "\\",
+    ];
+    let mut builder = GraphData::builder();
+    for (index, label) in labels.iter().enumerate() {
+        builder = builder.node(format!("n{index}")).label(*label);
+    }
+    let graph = builder.build();
+    let reparsed = parse_graph(&graph.to_dot()).unwrap();
+    for (index, label) in labels.iter().enumerate() {
+        let node = reparsed.find_node(&format!("n{index}")).unwrap();
+        assert_eq!(node.label.as_deref(), Some(*label));
+    }
+}
+
+#[test]
+fn ids_ending_in_backslashes_survive() {
+    let graph = round_trip(r#"digraph { "a\\" -> "b\\\\"; "c\\\"" [label="d\\"] }"#);
+    for id in ["a\\", "b\\\\", "c\\\""] {
+        assert!(graph.find_node(id).is_some(), "{id}");
+    }
+    assert_eq!(
+        graph.find_node("c\\\"").unwrap().label.as_deref(),
+        Some("d\\")
+    );
+}
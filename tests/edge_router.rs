@@ -0,0 +1,300 @@
+//! Edge routing: curves that miss nodes, and routes around those that can't be missed
+use dioxus_plumb::edge_router::*;
+use dioxus_plumb::edge_routing::EdgeRouting;
+use dioxus_plumb::perfect_arrows::*;
+use proptest::prelude::*;
+
+fn rect(x: f64, y: f64, w: f64, h: f64) -> Shape {
+    Shape::Rect {
+        pos: Pos2 { x, y },
+        size: Vec2 { x: w, y: h },
+        radius: 0.0,
+    }
+}
+
+// Obstacles for the named boxes, laid out as (id, x, y, w, h)
+fn obstacles(boxes: &[(&str, f64, f64, f64, f64)]) -> Obstacles {
+    let mut obstacles = Obstacles::new();
+    for &(id, x, y, w, h) in boxes {
+        obstacles.insert(id, Pos2 { x, y }, Vec2 { x: w, y: h });
+    }
+    obstacles
+}
+
+#[test]
+fn clear_edges_keep_their_curve() {
+    let start = rect(0.0, 0.0, 50.0, 30.0);
+    let end = rect(300.0, 0.0, 50.0, 30.0);
+    let graph = obstacles(&[
+        ("a", 0.0, 0.0, 50.0, 30.0),
+        ("b", 300.0, 0.0, 50.0, 30.0),
+        ("c", 150.0, 200.0, 50.0, 30.0),
+    ]);
+    let options = ArrowOptions::default();
+    let path = route_curved(&start, &end, &options, &graph, ["a", "b"]);
+    let curve = ArrowPath::from(&get_shape_to_shape_arrow(&start, &end, options));
+    assert_eq!(path, curve);
+}
+
+#[test]
+fn edges_bend_around_a_node_in_the_way() {
+    let start = rect(0.0, 100.0, 50.0, 30.0);
+    let end = rect(300.0, 100.0, 50.0, 30.0);
+    let graph = obstacles(&[
+        ("a", 0.0, 100.0, 50.0, 30.0),
+        ("b", 300.0, 100.0, 50.0, 30.0),
+        ("c", 150.0, 100.0, 50.0, 30.0),
+    ]);
+    let path = route_curved(&start, &end, &ArrowOptions::default(), &graph, ["a", "b"]);
+    assert_eq!(graph.crossings(&path, &["a", "b"]), 0);
+}
+
+#[test]
+fn edges_route_around_a_wall() {
+    let start = rect(0.0, 500.0, 50.0, 30.0);
+    let end = rect(400.0, 500.0, 50.0, 30.0);
+    // Too tall for any curve to bow around
+    let graph = obstacles(&[
+        ("a", 0.0, 500.0, 50.0, 30.0),
+        ("b", 400.0, 500.0, 50.0, 30.0),
+        ("wall", 200.0, 0.0, 20.0, 1_000.0),
+    ]);
+    let options = ArrowOptions::default();
+    let path = route_curved(&start, &end, &options, &graph, ["a", "b"]);
+    let curve = ArrowPath::from(&get_shape_to_shape_arrow(&start, &end, options));
+
+    assert_eq!(graph.crossings(&path, &["a", "b"]), 0);
+    assert_eq!(path.start, curve.start);
+    assert_eq!(path.end(), curve.end());
+}
+
+#[test]
+fn routes_around_a_wall_stay_out_of_their_own_nodes() {
+    let left = rect(0.0, 500.0, 50.0, 30.0);
+    let right = rect(400.0, 500.0, 50.0, 30.0);
+    let graph = obstacles(&[
+        ("a", 0.0, 500.0, 50.0, 30.0),
+        ("b", 400.0, 500.0, 50.0, 30.0),
+        ("wall", 200.0, 0.0, 20.0, 1_000.0),
+    ]);
+    let ends = [graph.get("a").unwrap(), graph.get("b").unwrap()];
+    let there = route_curved(&left, &right, &ArrowOptions::default(), &graph, ["a", "b"]);
+    let back = route_curved(&right, &left, &ArrowOptions::default(), &graph, ["b", "a"]);
+    for path in [&there, &back] {
+        for p in path.flatten(16) {
+            assert!(
+                !ends.iter().any(|end| end.contains(p)),
+                "{p:?} is inside an end"
+            );
+        }
+    }
+}
+
+#[test]
+fn crossings_skip_ignored_nodes() {
+    let graph = obstacles(&[("a", 0.0, 0.0, 10.0, 10.0), ("b", 20.0, 0.0, 10.0, 10.0)]);
+    let path = ArrowPath {
+        start: Pos2 { x: 5.0, y: 5.0 },
+        segments: vec![PathSegment::Line {
+            to: Pos2 { x: 25.0, y: 5.0 },
+        }],
+    };
+    assert_eq!(graph.crossings(&path, &[]), 2);
+    assert_eq!(graph.crossings(&path, &["a"]), 1);
+    assert_eq!(graph.crossings(&path, &["a", "b"]), 0);
+}
+
+// Whether every straight piece of the path runs horizontally or vertically
+fn is_orthogonal(path: &ArrowPath) -> bool {
+    let mut from = path.start;
+    path.segments.iter().all(|segment| {
+        let ok = match segment {
+            PathSegment::Line { to } => {
+                (to.x - from.x).abs() < 1e-6 || (to.y - from.y).abs() < 1e-6
+            }
+            _ => true,
+        };
+        from = segment.end();
+        ok
+    })
+}
+
+#[test]
+fn orthogonal_edges_turn_at_right_angles_around_nodes() {
+    let start = rect(0.0, 100.0, 50.0, 30.0);
+    let end = rect(300.0, 200.0, 50.0, 30.0);
+    let graph = obstacles(&[
+        ("a", 0.0, 100.0, 50.0, 30.0),
+        ("b", 300.0, 200.0, 50.0, 30.0),
+        ("c", 150.0, 50.0, 50.0, 300.0),
+    ]);
+    let path = route_orthogonal(&start, &end, &graph, ["a", "b"], 0);
+
+    assert!(is_orthogonal(&path));
+    assert_eq!(graph.crossings(&path, &["a", "b"]), 0);
+    // Leaves the right side and enters the left side
+    assert_eq!(path.start, Pos2 { x: 50.0, y: 115.0 });
+    assert_eq!(path.end(), Pos2 { x: 300.0, y: 215.0 });
+}
+
+#[test]
+fn parallel_orthogonal_edges_get_their_own_channels() {
+    let start = rect(0.0, 0.0, 50.0, 30.0);
+    let end = rect(300.0, 200.0, 50.0, 30.0);
+    let graph = obstacles(&[("a", 0.0, 0.0, 50.0, 30.0), ("b", 300.0, 200.0, 50.0, 30.0)]);
+    let first = route_orthogonal(&start, &end, &graph, ["a", "b"], 0);
+    let second = route_orthogonal(&start, &end, &graph, ["a", "b"], 1);
+
+    assert!(is_orthogonal(&second));
+    assert_ne!(first.start, second.start);
+    assert_ne!(first.end(), second.end());
+    // No point of one lies on the other
+    let second_points = second.flatten(8);
+    assert!(first.flatten(8).iter().all(|p| second_points
+        .iter()
+        .all(|q| (p.x - q.x).abs() > 1.0 || (p.y - q.y).abs() > 1.0)));
+}
+
+// Rows of 40x20 boxes 100px apart, nudged a little per row and column so no two share
+// a grid line. Far more nodes than one routing grid over the whole graph can hold.
+fn large_graph(
+    rows: usize,
+    columns: usize,
+) -> (Obstacles, impl Fn(usize, usize) -> (String, Shape)) {
+    let node = |row: usize, column: usize| {
+        let x = column as f64 * 100.0 + row as f64 * 0.6;
+        let y = row as f64 * 100.0 + column as f64 * 0.6;
+        (format!("n{row}_{column}"), rect(x, y, 40.0, 20.0))
+    };
+    let mut graph = Obstacles::new();
+    for row in 0..rows {
+        for column in 0..columns {
+            let (id, shape) = node(row, column);
+            let (pos, size) = shape.bounds();
+            graph.insert(id, pos, size);
+        }
+    }
+    (graph, node)
+}
+
+#[test]
+fn large_graphs_still_route_around_nodes() {
+    let (graph, node) = large_graph(30, 30);
+    // The node between the two ends is in the way of any straight or Z-shaped route
+    let (a, start) = node(12, 10);
+    let (b, end) = node(12, 12);
+
+    let path = route_orthogonal(&start, &end, &graph, [&a, &b], 0);
+    assert!(is_orthogonal(&path));
+    assert_eq!(graph.crossings(&path, &[&a, &b]), 0);
+
+    let path = route_curved(&start, &end, &ArrowOptions::default(), &graph, [&a, &b]);
+    assert_eq!(graph.crossings(&path, &[&a, &b]), 0);
+}
+
+#[test]
+fn hundreds_of_orthogonal_edges_avoid_nodes() {
+    let (graph, node) = large_graph(20, 20);
+    let mut edges = 0;
+    for row in (0..20).step_by(2) {
+        for column in 0..18 {
+            // Two columns over, or diagonally down past a row of nodes
+            for (to_row, to_column) in [(row, column + 2), (row + 1, column + 2)] {
+                let (a, start) = node(row, column);
+                let (b, end) = node(to_row.min(19), to_column);
+                let path = route_orthogonal(&start, &end, &graph, [&a, &b], 0);
+                assert!(is_orthogonal(&path), "{a} -> {b}");
+                assert_eq!(graph.crossings(&path, &[&a, &b]), 0, "{a} -> {b}");
+                edges += 1;
+            }
+        }
+    }
+    assert!(edges >= 300);
+}
+
+#[test]
+fn splines_attribute_selects_the_routing() {
+    let graph = dioxus_plumb::graph_data::parse_graph("digraph { splines=ortho; a -> b }").unwrap();
+    assert_eq!(
+        EdgeRouting::from_attributes(&graph.attributes),
+        EdgeRouting::Orthogonal
+    );
+    assert_eq!(EdgeRouting::from_slice("line"), EdgeRouting::Straight);
+    assert_eq!(EdgeRouting::from_slice("\"true\""), EdgeRouting::Curved);
+}
+
+#[test]
+fn self_loops_stand_out_from_the_right_side() {
+    let node = rect(100.0, 100.0, 60.0, 30.0);
+    let graph = obstacles(&[("a", 100.0, 100.0, 60.0, 30.0)]);
+    let path = route_self_loop(&node, &graph, "a", 0, EdgeRouting::Curved);
+
+    assert_ne!(path.start, path.end());
+    assert!(on_outline(path.start, 100.0, 100.0, 60.0, 30.0));
+    assert!(on_outline(path.end(), 100.0, 100.0, 60.0, 30.0));
+    let apex = path.point_at(0.5);
+    assert!((apex.x - 190.0).abs() < 1e-6 && (apex.y - 115.0).abs() < 1e-6);
+}
+
+#[test]
+fn self_loops_avoid_neighbors() {
+    let node = rect(100.0, 100.0, 60.0, 30.0);
+    let graph = obstacles(&[
+        ("a", 100.0, 100.0, 60.0, 30.0),
+        ("right", 180.0, 100.0, 60.0, 30.0),
+    ]);
+    let path = route_self_loop(&node, &graph, "a", 0, EdgeRouting::Curved);
+    let (pos, _) = path.bounding_box();
+    assert!(pos.y < 100.0, "loop should move to the top");
+    assert_eq!(graph.crossings(&path, &["a"]), 0);
+}
+
+#[test]
+fn self_loops_on_one_node_nest() {
+    let node = rect(100.0, 100.0, 60.0, 30.0);
+    let graph = obstacles(&[("a", 100.0, 100.0, 60.0, 30.0)]);
+    for routing in [EdgeRouting::Curved, EdgeRouting::Orthogonal] {
+        let inner = route_self_loop(&node, &graph, "a", 0, routing).bounding_box();
+        let outer = route_self_loop(&node, &graph, "a", 1, routing).bounding_box();
+        assert!(outer.0.x + outer.1.x > inner.0.x + inner.1.x);
+        assert!(outer.0.y < inner.0.y && outer.0.y + outer.1.y > inner.0.y + inner.1.y);
+    }
+}
+
+// Whether `p` is on the edge of the box, within rounding
+fn on_outline(p: Pos2, x: f64, y: f64, w: f64, h: f64) -> bool {
+    let within = |v: f64, min: f64, max: f64| v >= min - 1e-6 && v <= max + 1e-6;
+    let near = |v: f64, at: f64| (v - at).abs() < 1e-6;
+    within(p.x, x, x + w)
+        && within(p.y, y, y + h)
+        && (near(p.x, x) || near(p.x, x + w) || near(p.y, y) || near(p.y, y + h))
+}
+
+proptest! {
+    #[test]
+    fn routes_run_between_the_outlines(
+        boxes in prop::collection::vec((0.0..800.0, 0.0..800.0, 1.0..200.0, 1.0..200.0), 0..12),
+        (x0, y0, x1, y1) in (0.0..800.0, 0.0..800.0, 0.0..800.0, 0.0..800.0),
+    ) {
+        let start = rect(x0, y0, 40.0, 20.0);
+        let end = rect(x1, y1, 40.0, 20.0);
+        let mut graph = obstacles(&[("a", x0, y0, 40.0, 20.0), ("b", x1, y1, 40.0, 20.0)]);
+        for (i, (x, y, w, h)) in boxes.into_iter().enumerate() {
+            graph.insert(format!("n{i}"), Pos2 { x, y }, Vec2 { x: w, y: h });
+        }
+        let path = route_curved(&start, &end, &ArrowOptions::default(), &graph, ["a", "b"]);
+
+        prop_assert!(on_outline(path.start, x0, y0, 40.0, 20.0));
+        prop_assert!(on_outline(path.end(), x1, y1, 40.0, 20.0));
+        for p in path.flatten(8) {
+            prop_assert!(p.x.is_finite() && p.y.is_finite());
+        }
+
+        for channel in 0..3 {
+            let path = route_orthogonal(&start, &end, &graph, ["a", "b"], channel);
+            prop_assert!(is_orthogonal(&path));
+            prop_assert!(on_outline(path.start, x0, y0, 40.0, 20.0));
+            prop_assert!(on_outline(path.end(), x1, y1, 40.0, 20.0));
+        }
+    }
+}
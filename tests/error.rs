@@ -0,0 +1,103 @@
+//! Parse errors point at the offending DOT source
+use dioxus_plumb::error::{ParseError, Span};
+use dioxus_plumb::graph_data::{parse_graph, parse_graph_with_options, ParseOptions};
+
+#[test]
+fn syntax_errors_point_at_the_bad_token() {
+    let source = "digraph {\n  a -> ;\n}";
+    let err = parse_graph(source).unwrap_err();
+    assert!(matches!(err, ParseError::Syntax { .. }));
+    let span = err.span();
+    assert_eq!((span.line, span.column), (2, 8));
+    assert_eq!(&source[span.start..span.start + 1], ";");
+    assert!(err.to_string().starts_with("Syntax error at 2:8: "));
+}
+
+#[test]
+fn unknown_nodes_point_at_their_first_use() {
+    let source = "digraph {\n  a;\n  a -> \"b c\";\n}";
+    let options = ParseOptions {
+        implicit_nodes: false,
+    };
+    let err = parse_graph_with_options(source, &options).unwrap_err();
+    let ParseError::UnknownNode { node, span } = &err else {
+        panic!("expected an unknown node, got {err:?}");
+    };
+    assert_eq!(node, "b c");
+    assert_eq!((span.line, span.column), (3, 8));
+    assert_eq!(&source[span.start..span.end], "\"b c\"");
+    assert_eq!(err.to_string(), "Unknown node \"b c\" in edge at 3:8");
+}
+
+#[test]
+fn unknown_nodes_skip_labels_comments_and_html() {
+    let source = "digraph {\n  a [label=\"b\"]; // b\n  c [label=<<b>b</b>>];\n  a -> b;\n}";
+    let options = ParseOptions {
+        implicit_nodes: false,
+    };
+    let err = parse_graph_with_options(source, &options).unwrap_err();
+    let span = err.span();
+    assert_eq!((span.line, span.column), (4, 8));
+    assert_eq!(&source[span.start..span.end], "b");
+}
+
+#[test]
+fn duplicate_subgraphs_point_at_the_declaration_under_another_parent() {
+    let source = "digraph {\n  x [label=\"subgraph cluster_a\"];\n  subgraph cluster_a { a }\n  \
+                  subgraph cluster_b { subgraph cluster_a { b } }\n}";
+    let err = parse_graph(source).unwrap_err();
+    let ParseError::DuplicateSubgraph { id, span } = &err else {
+        panic!("expected a duplicate subgraph, got {err:?}");
+    };
+    assert_eq!(id, "cluster_a");
+    let start = source.rfind("cluster_a").unwrap();
+    assert_eq!(*span, Span::from_offsets(source, start, start + 9));
+    assert_eq!((span.line, span.column), (4, 33));
+    assert_eq!(
+        err.to_string(),
+        "Duplicate subgraph id \"cluster_a\" at 4:33"
+    );
+}
+
+#[test]
+fn named_subgraph_endpoints_are_unsupported() {
+    let source = "digraph {\n  a -> subgraph \"s t\" { b c }\n}";
+    let err = parse_graph(source).unwrap_err();
+    let ParseError::Unsupported { feature, span } = &err else {
+        panic!("expected an unsupported feature, got {err:?}");
+    };
+    assert_eq!(feature, "subgraphs with an id as edge endpoints");
+    assert_eq!(&source[span.start..span.end], "\"s t\"");
+    assert_eq!(
+        err.to_string(),
+        "Unsupported feature at 2:17: subgraphs with an id as edge endpoints"
+    );
+    // Without an id nothing is lost
+    assert!(parse_graph("digraph { a -> subgraph { b c } }").is_ok());
+}
+
+#[test]
+fn only_one_graph_is_read() {
+    let source = "digraph { a }\n// next\ndigraph { b }";
+    let err = parse_graph(source).unwrap_err();
+    assert!(matches!(err, ParseError::Unsupported { .. }), "{err:?}");
+    assert_eq!((err.span().line, err.span().column), (3, 1));
+
+    let err = parse_graph("digraph { a } b").unwrap_err();
+    assert!(matches!(err, ParseError::Syntax { .. }), "{err:?}");
+    assert_eq!(err.span().column, 15);
+    assert!(parse_graph("digraph { a } /* done */").is_ok());
+}
+
+#[test]
+fn error_spans_are_never_before_the_first_column() {
+    let options = ParseOptions {
+        implicit_nodes: false,
+    };
+    // The id is only written escaped, so it can't be found verbatim
+    let source = r#"digraph { a; a -> "say \"hi\"" }"#;
+    let err = parse_graph_with_options(source, &options).unwrap_err();
+    let span = err.span();
+    assert!(span.line >= 1 && span.column >= 1);
+    assert_eq!(&source[span.start..span.end], r#""say \"hi\"""#);
+}
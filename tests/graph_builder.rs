@@ -0,0 +1,97 @@
+//! Graphs built in code match the same graph parsed from DOT
+use dioxus_plumb::edge_routing::EdgeRouting;
+use dioxus_plumb::graph_data::{parse_graph, GraphData};
+use dioxus_plumb::graph_kind::GraphKind;
+use dioxus_plumb::rankdir::RankDir;
+
+#[test]
+fn builder_matches_parsed_dot() {
+    let built = GraphData::builder()
+        .label("Dashboard")
+        .rankdir(RankDir::LR)
+        .splines(EdgeRouting::Orthogonal)
+        .node_default("shape", "box")
+        .edge_default("color", "gray")
+        .node("a")
+        .label("A")
+        .attr("nodetype", "entry")
+        .subgraph("cluster_x", |sg| {
+            sg.label("X")
+                .graph_attr("style", "dashed")
+                .node("b")
+                .node_default("shape", "circle")
+                .node("c")
+                .edge("b", "c")
+        })
+        .edge("a", "b")
+        .label("uses")
+        .edge("a", "b")
+        .edge("a", "d")
+        .build();
+
+    let parsed = parse_graph(
+        r#"digraph {
+            label="Dashboard";
+            rankdir=LR;
+            splines=ortho;
+            node [shape=box];
+            edge [color=gray];
+            a [label="A", nodetype=entry];
+            subgraph cluster_x {
+                label="X";
+                style=dashed;
+                b;
+                node [shape=circle];
+                c;
+                b -> c;
+            }
+            a -> b [label="uses"];
+            a -> b;
+            a -> d;
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(built, parsed);
+    // Same prefixed ids and implicit nodes
+    assert!(built.find_node("cluster_x-b").is_some());
+    assert!(built.find_node("d").is_some());
+    let ids: Vec<&str> = built.edges.iter().map(|edge| edge.id.as_str()).collect();
+    assert_eq!(ids, ["b-c", "a-b", "a-b-1", "a-d"]);
+}
+
+#[test]
+fn builder_ids_need_no_quoting() {
+    let built = GraphData::builder()
+        .kind(GraphKind::Undirected)
+        .strict(true)
+        .node("say \"hi\"")
+        .label("a -> b; }")
+        .edge("say \"hi\"", "with space")
+        .edge("with space", "say \"hi\"")
+        .build();
+
+    let parsed = parse_graph(
+        r#"strict graph { "say \"hi\"" [label="a -> b; }"]; "say \"hi\"" -- "with space"; "with space" -- "say \"hi\"" }"#,
+    )
+    .unwrap();
+    assert_eq!(built, parsed);
+    assert_eq!(built.edges.len(), 1);
+    assert_eq!(
+        built.find_node("say \"hi\"").unwrap().label.as_deref(),
+        Some("a -> b; }")
+    );
+}
+
+#[test]
+fn html_labels_from_the_builder_are_parsed() {
+    let built = GraphData::builder()
+        .node("t")
+        .html_label("<TABLE><TR><TD PORT=\"p\">x</TD></TR></TABLE>")
+        .build();
+    let parsed =
+        parse_graph(r#"digraph { t [label=<<TABLE><TR><TD PORT="p">x</TD></TR></TABLE>>] }"#)
+            .unwrap();
+    assert_eq!(built, parsed);
+    assert!(built.nodes[0].html_label.is_some());
+}
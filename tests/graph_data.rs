@@ -0,0 +1,257 @@
+//! DOT attributes survive parsing onto nodes, edges and graphs
+use dioxus_plumb::edge_dir::EdgeDir;
+use dioxus_plumb::graph_data::{parse_graph, GraphData, NodeData};
+use dioxus_plumb::graph_kind::GraphKind;
+
+fn node<'a>(graph: &'a GraphData, id: &str) -> &'a NodeData {
+    graph
+        .find_node(id)
+        .unwrap_or_else(|| panic!("node {id} not found"))
+}
+
+#[test]
+fn node_edge_and_graph_attributes_are_kept() {
+    let graph = parse_graph(
+        r#"digraph {
+            rankdir=LR;
+            graph [bgcolor=white];
+            a [shape=box, color=red, URL="https://example.com", nodetype=entry];
+            a -> b [color=blue, penwidth=2];
+        }"#,
+    )
+    .unwrap();
+
+    let a = node(&graph, "a");
+    assert_eq!(a.attributes.get("shape"), Some("box"));
+    assert_eq!(a.attributes.get("URL"), Some("https://example.com"));
+    assert_eq!(a.attributes.get("nodetype"), Some("entry"));
+    let keys: Vec<&str> = a.attributes.iter().map(|(key, _)| key).collect();
+    assert_eq!(keys, ["shape", "color", "URL", "nodetype"]);
+
+    assert_eq!(graph.edges[0].attributes.get("color"), Some("blue"));
+    assert_eq!(graph.edges[0].attributes.get("penwidth"), Some("2"));
+    assert_eq!(graph.attributes.get("rankdir"), Some("LR"));
+    assert_eq!(graph.attributes.get("bgcolor"), Some("white"));
+}
+
+#[test]
+fn repeated_node_declarations_merge() {
+    let graph = parse_graph("digraph { a [color=red]; a [label=x]; a [color=blue] }").unwrap();
+    assert_eq!(graph.nodes.len(), 1);
+    let a = node(&graph, "a");
+    assert_eq!(a.label.as_deref(), Some("x"));
+    assert_eq!(a.attributes.get("color"), Some("blue"));
+}
+
+#[test]
+fn nodes_mentioned_in_a_cluster_move_into_it() {
+    let graph =
+        parse_graph("digraph { a [color=red]; subgraph cluster_x { a [label=A] } }").unwrap();
+    assert!(graph.nodes.is_empty());
+    let a = node(&graph, "cluster_x-a");
+    assert_eq!(a.attributes.get("color"), Some("red"));
+    assert_eq!(a.label.as_deref(), Some("A"));
+}
+
+#[test]
+fn nodes_in_edge_subgraphs_keep_their_attributes() {
+    let graph =
+        parse_graph("digraph { a -> { node [shape=box]; b [color=red]; c } -> d }").unwrap();
+    assert_eq!(node(&graph, "b").attributes.get("color"), Some("red"));
+    assert_eq!(node(&graph, "b").attributes.get("shape"), Some("box"));
+    assert_eq!(node(&graph, "c").attributes.get("shape"), Some("box"));
+    // The defaults stay inside the braces
+    assert_eq!(node(&graph, "d").attributes.get("shape"), None);
+    let pairs: Vec<(&str, &str)> = graph
+        .edges
+        .iter()
+        .map(|edge| (edge.source.as_str(), edge.target.as_str()))
+        .collect();
+    assert_eq!(pairs, [("a", "b"), ("a", "c"), ("b", "d"), ("c", "d")]);
+}
+
+#[test]
+fn defaults_apply_to_later_statements_only() {
+    let graph = parse_graph(
+        "digraph { a; node [shape=box]; edge [color=gray]; b; a -> b; c [shape=circle] }",
+    )
+    .unwrap();
+    assert_eq!(node(&graph, "a").attributes.get("shape"), None);
+    assert_eq!(node(&graph, "b").attributes.get("shape"), Some("box"));
+    // Explicit attributes win over defaults
+    assert_eq!(node(&graph, "c").attributes.get("shape"), Some("circle"));
+    assert_eq!(graph.edges[0].attributes.get("color"), Some("gray"));
+}
+
+#[test]
+fn defaults_are_scoped_to_their_subgraph() {
+    let graph = parse_graph(
+        r#"digraph {
+            node [color=red];
+            graph [fontname=Arial, label=Top];
+            subgraph cluster_x {
+                node [shape=box];
+                edge [style=dashed];
+                x;
+                x -> y;
+            }
+            z;
+            z -> x;
+        }"#,
+    )
+    .unwrap();
+
+    // Inherited from the parent, plus the subgraph's own
+    let x = node(&graph, "cluster_x-x");
+    assert_eq!(x.attributes.get("color"), Some("red"));
+    assert_eq!(x.attributes.get("shape"), Some("box"));
+    // Nothing leaks back out
+    let z = node(&graph, "z");
+    assert_eq!(z.attributes.get("color"), Some("red"));
+    assert_eq!(z.attributes.get("shape"), None);
+    assert_eq!(graph.edges[0].attributes.get("style"), Some("dashed"));
+    assert_eq!(graph.edges[1].attributes.get("style"), None);
+
+    // Graph defaults reach the subgraph, all but the label
+    let cluster = &graph.subgraphs[0];
+    assert_eq!(cluster.attributes.get("fontname"), Some("Arial"));
+    assert_eq!(cluster.label, None);
+}
+
+#[test]
+fn implicit_nodes_get_the_defaults_where_they_were_first_mentioned() {
+    let graph = parse_graph("digraph { subgraph s { node [shape=box]; a -> b } c -> d }").unwrap();
+    assert_eq!(node(&graph, "a").attributes.get("shape"), Some("box"));
+    assert_eq!(node(&graph, "c").attributes.get("shape"), None);
+}
+
+#[test]
+fn subgraphs_keep_their_declared_ids() {
+    let dot = |extra: &str| {
+        format!("digraph {{ {extra} subgraph cluster_dht {{ a }} subgraph group {{ b }} }}")
+    };
+    let graph = parse_graph(&dot("")).unwrap();
+    assert_eq!(graph.subgraphs[0].id, "cluster_dht");
+    assert!(graph.subgraphs[0].is_cluster);
+    assert_eq!(graph.subgraphs[1].id, "group");
+    assert!(!graph.subgraphs[1].is_cluster);
+    assert!(graph.find_node("cluster_dht-a").is_some());
+
+    // Stable when another subgraph is inserted before
+    let graph = parse_graph(&dot("subgraph cluster_first { z }")).unwrap();
+    assert_eq!(graph.subgraphs[1].id, "cluster_dht");
+    assert!(graph.find_node("cluster_dht-a").is_some());
+}
+
+#[test]
+fn subgraph_ids_are_sanitized_for_the_dom() {
+    let graph = parse_graph(
+        r#"digraph { subgraph "cluster my-group" { a } subgraph "1st" { b } subgraph { c } subgraph g { cluster=true; d } }"#,
+    )
+    .unwrap();
+    let ids: Vec<&str> = graph.subgraphs.iter().map(|sg| sg.id.as_str()).collect();
+    assert_eq!(ids, ["cluster_my_group", "subgraph_1st", "subgraph_2", "g"]);
+    assert!(graph.subgraphs[0].is_cluster);
+    assert!(!graph.subgraphs[2].is_cluster);
+    // `cluster=true` makes any subgraph a cluster
+    assert!(graph.subgraphs[3].is_cluster);
+}
+
+#[test]
+fn ids_alike_once_sanitized_get_a_suffix() {
+    let graph = parse_graph(
+        r#"digraph { subgraph "cluster-a" { a } subgraph cluster_a { b } subgraph "cluster a" { c } }"#,
+    )
+    .unwrap();
+    let ids: Vec<&str> = graph.subgraphs.iter().map(|sg| sg.id.as_str()).collect();
+    assert_eq!(ids, ["cluster_a", "cluster_a_1", "cluster_a_2"]);
+    assert!(graph.find_node("cluster_a_1-b").is_some());
+}
+
+#[test]
+fn reopened_subgraphs_are_merged() {
+    let graph = parse_graph(
+        r#"digraph {
+            subgraph cluster_a { label="A"; x }
+            y;
+            subgraph "cluster_a" { color=red; z }
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(graph.subgraphs.len(), 1);
+    let cluster = &graph.subgraphs[0];
+    let nodes: Vec<&str> = cluster.nodes.iter().map(|n| n.id.as_str()).collect();
+    assert_eq!(nodes, ["cluster_a-x", "cluster_a-z"]);
+    assert_eq!(cluster.label.as_deref(), Some("A"));
+    assert_eq!(cluster.attributes.get("color"), Some("red"));
+}
+
+fn edge_ids(graph: &GraphData) -> Vec<&str> {
+    graph.edges.iter().map(|edge| edge.id.as_str()).collect()
+}
+
+#[test]
+fn parallel_edges_get_unique_ids() {
+    let graph = parse_graph(
+        "digraph { a -> b [label=x]; a -> b [label=y]; a -> b; b -> a; a -> c [id=mine] }",
+    )
+    .unwrap();
+    assert_eq!(edge_ids(&graph), ["a-b", "a-b-1", "a-b-2", "b-a", "mine"]);
+    assert_eq!(graph.edges[1].label.as_deref(), Some("y"));
+}
+
+#[test]
+fn user_edge_ids_are_kept_unique() {
+    let graph = parse_graph("digraph { a -> b [id=e]; b -> c [id=e] }").unwrap();
+    assert_eq!(edge_ids(&graph), ["e", "e-1"]);
+}
+
+#[test]
+fn strict_graphs_merge_repeated_edges() {
+    let graph =
+        parse_graph("strict digraph { a -> b [color=red]; a -> b [label=x]; b -> a }").unwrap();
+    assert!(graph.strict);
+    assert_eq!(edge_ids(&graph), ["a-b", "b-a"]);
+    assert_eq!(graph.edges[0].attributes.get("color"), Some("red"));
+    assert_eq!(graph.edges[0].label.as_deref(), Some("x"));
+
+    // Undirected edges are the same either way round
+    let graph = parse_graph("strict graph { a -- b; b -- a }").unwrap();
+    assert_eq!(edge_ids(&graph), ["a-b"]);
+}
+
+#[test]
+fn graph_kind_and_dir_pick_the_arrowheads() {
+    let graph = parse_graph(
+        "digraph { a -> b; a -> c [dir=back]; a -> d [dir=both]; a -> e [dir=none]; a -> f [dir=bogus] }",
+    )
+    .unwrap();
+    assert_eq!(graph.kind, GraphKind::Directed);
+    assert!(!graph.strict);
+    let dirs: Vec<EdgeDir> = graph.edges.iter().map(|edge| edge.dir).collect();
+    assert_eq!(
+        dirs,
+        [
+            EdgeDir::Forward,
+            EdgeDir::Back,
+            EdgeDir::Both,
+            EdgeDir::None,
+            EdgeDir::Forward
+        ]
+    );
+    assert!(graph.edges[2].dir.has_start_arrow() && graph.edges[2].dir.has_end_arrow());
+
+    // Undirected graphs draw plain connectors unless told otherwise
+    let graph = parse_graph("graph { a -- b; a -- c [dir=forward] }").unwrap();
+    assert_eq!(graph.kind, GraphKind::Undirected);
+    assert_eq!(graph.edges[0].dir, EdgeDir::None);
+    assert!(!graph.edges[0].dir.has_end_arrow());
+    assert_eq!(graph.edges[1].dir, EdgeDir::Forward);
+}
+
+#[test]
+fn subgraphs_share_the_kind_of_their_root() {
+    let graph = parse_graph("strict graph { subgraph cluster_x { a -- b } }").unwrap();
+    assert_eq!(graph.subgraphs[0].kind, GraphKind::Undirected);
+    assert!(graph.subgraphs[0].strict);
+}
@@ -0,0 +1,99 @@
+//! Node lookups, neighbours and lineage over parsed graphs
+use dioxus_plumb::graph_data::parse_graph;
+
+const DOT: &str = r#"digraph {
+    a -> b;
+    a -> b;
+    subgraph cluster_x {
+        c;
+        subgraph cluster_y { d }
+    }
+    b -> c -> d;
+    a -> e;
+    f;
+}"#;
+
+fn ids<'a>(
+    nodes: impl IntoIterator<Item = &'a dioxus_plumb::graph_data::NodeData>,
+) -> Vec<&'a str> {
+    nodes.into_iter().map(|node| node.id.as_str()).collect()
+}
+
+#[test]
+fn nodes_are_found_at_any_depth() {
+    let graph = parse_graph(DOT).unwrap();
+    assert!(graph.find_node("cluster_x-cluster_y-d").is_some());
+    assert!(graph.find_node("d").is_none());
+    assert!(graph.find_node("missing").is_none());
+
+    let mut all = ids(graph.all_nodes());
+    all.sort();
+    assert_eq!(
+        all,
+        ["a", "b", "cluster_x-c", "cluster_x-cluster_y-d", "e", "f"]
+    );
+}
+
+#[test]
+fn subgraph_paths_list_the_outermost_first() {
+    let graph = parse_graph(DOT).unwrap();
+    let index = graph.index();
+    let path = |id| {
+        index
+            .subgraph_path(id)
+            .map(|path| path.iter().map(|g| g.id.as_str()).collect::<Vec<_>>())
+    };
+    assert_eq!(path("a"), Some(vec![]));
+    assert_eq!(path("cluster_x-c"), Some(vec!["cluster_x"]));
+    assert_eq!(
+        path("cluster_x-cluster_y-d"),
+        Some(vec!["cluster_x", "cluster_y"])
+    );
+    assert_eq!(path("missing"), None);
+    assert_eq!(index.node("f").map(|node| node.id.as_str()), Some("f"));
+}
+
+#[test]
+fn edges_are_indexed_both_ways() {
+    let graph = parse_graph(DOT).unwrap();
+    let index = graph.index();
+    let edge_ids = |edges: &[&dioxus_plumb::edge_renderer::EdgeData]| {
+        edges.iter().map(|edge| edge.id.clone()).collect::<Vec<_>>()
+    };
+    assert_eq!(edge_ids(index.out_edges("a")), ["a-b", "a-b-1", "a-e"]);
+    assert_eq!(edge_ids(index.in_edges("b")), ["a-b", "a-b-1"]);
+    assert!(index.out_edges("f").is_empty());
+    assert!(index.in_edges("missing").is_empty());
+}
+
+#[test]
+fn parallel_edges_give_one_neighbour() {
+    let graph = parse_graph(DOT).unwrap();
+    let index = graph.index();
+    assert_eq!(index.successors("a"), ["b", "e"]);
+    assert_eq!(index.predecessors("b"), ["a"]);
+    assert!(index.predecessors("a").is_empty());
+}
+
+#[test]
+fn lineage_is_nearest_first_and_excludes_the_node() {
+    let graph = parse_graph(DOT).unwrap();
+    let index = graph.index();
+    assert_eq!(
+        index.descendants("a"),
+        ["b", "e", "cluster_x-c", "cluster_x-cluster_y-d"]
+    );
+    assert_eq!(
+        index.ancestors("cluster_x-cluster_y-d"),
+        ["cluster_x-c", "b", "a"]
+    );
+    assert!(index.descendants("f").is_empty());
+}
+
+#[test]
+fn cycles_do_not_repeat_nodes() {
+    let graph = parse_graph("digraph { a -> b -> c -> a }").unwrap();
+    let index = graph.index();
+    assert_eq!(index.descendants("a"), ["b", "c"]);
+    assert_eq!(index.ancestors("a"), ["c", "b"]);
+}
@@ -0,0 +1,125 @@
+//! HTML-like labels: the supported tags, entities, nesting and what gets dropped
+use dioxus_plumb::graph_data::parse_graph;
+use dioxus_plumb::html_label::{parse_html_label, HtmlElement, HtmlNode, HtmlTag};
+
+fn element(node: &HtmlNode) -> &HtmlElement {
+    match node {
+        HtmlNode::Element(element) => element,
+        HtmlNode::Text(text) => panic!("expected an element, got text {text:?}"),
+    }
+}
+
+fn text(node: &HtmlNode) -> &str {
+    match node {
+        HtmlNode::Text(text) => text,
+        HtmlNode::Element(element) => panic!("expected text, got {:?}", element.tag),
+    }
+}
+
+#[test]
+fn text_styling_nests() {
+    let nodes = parse_html_label("plain <B>bold <i>both</I></b><br/>next");
+    assert_eq!(nodes.len(), 4);
+    assert_eq!(text(&nodes[0]), "plain ");
+    let bold = element(&nodes[1]);
+    assert_eq!(bold.tag, HtmlTag::B);
+    assert_eq!(text(&bold.children[0]), "bold ");
+    let italic = element(&bold.children[1]);
+    assert_eq!(italic.tag, HtmlTag::I);
+    assert_eq!(text(&italic.children[0]), "both");
+    assert_eq!(element(&nodes[2]).tag, HtmlTag::Br);
+    assert_eq!(text(&nodes[3]), "next");
+}
+
+#[test]
+fn entities_are_decoded_in_text_and_attributes() {
+    let nodes = parse_html_label(
+        r#"<font face="A&amp;B">&lt;tag&gt; &quot;q&quot; &apos; &#65;&#x42;&#X43;&nbsp;</font>"#,
+    );
+    let font = element(&nodes[0]);
+    assert_eq!(font.attributes.get("face"), Some("A&B"));
+    assert_eq!(text(&font.children[0]), "<tag> \"q\" ' ABC\u{a0}");
+}
+
+#[test]
+fn unknown_entities_stay_as_written() {
+    let nodes = parse_html_label("a &bogus; b & c &#xZZ; &#1114112;");
+    assert_eq!(text(&nodes[0]), "a &bogus; b & c &#xZZ; &#1114112;");
+}
+
+#[test]
+fn nested_tables_keep_their_structure_and_ports() {
+    let nodes = parse_html_label(
+        r#"<TABLE BORDER="0">
+            <TR><TD PORT="left">L</TD><TD>
+                <TABLE><TR><TD port='inner' COLSPAN=2>I</TD></TR></TABLE>
+            </TD></TR>
+        </TABLE>"#,
+    );
+    assert_eq!(nodes.len(), 1);
+    let table = element(&nodes[0]);
+    assert_eq!(table.tag, HtmlTag::Table);
+    assert_eq!(table.attributes.get("border"), Some("0"));
+
+    let rows: Vec<_> = table.child_elements().collect();
+    assert_eq!(rows.len(), 1);
+    let cells: Vec<_> = rows[0].child_elements().collect();
+    assert_eq!(cells.len(), 2);
+    assert_eq!(cells[0].port(), Some("left"));
+    assert_eq!(cells[1].port(), None);
+
+    let inner = cells[1].child_elements().next().unwrap();
+    assert_eq!(inner.tag, HtmlTag::Table);
+    let inner_cell = inner
+        .child_elements()
+        .next()
+        .unwrap()
+        .child_elements()
+        .next()
+        .unwrap();
+    assert_eq!(inner_cell.port(), Some("inner"));
+    assert_eq!(inner_cell.attributes.get("colspan"), Some("2"));
+    assert_eq!(text(&inner_cell.children[0]), "I");
+}
+
+#[test]
+fn unsafe_and_unknown_tags_are_dropped_but_their_text_kept() {
+    let nodes = parse_html_label(
+        r#"<script>alert(1)</script><a href="x" onclick="y">link</a><!-- <b>gone</b> -->end"#,
+    );
+    let texts: Vec<&str> = nodes.iter().map(text).collect();
+    assert_eq!(texts, ["alert(1)", "link", "end"]);
+}
+
+#[test]
+fn unbalanced_tags_are_repaired() {
+    // Unclosed elements are closed at the end
+    let nodes = parse_html_label("<b>a<i>b");
+    let bold = element(&nodes[0]);
+    assert_eq!(element(&bold.children[1]).tag, HtmlTag::I);
+
+    // Stray closing tags are ignored, closing an outer tag closes the inner ones
+    let nodes = parse_html_label("</u>x<b><i>y</b>z");
+    assert_eq!(text(&nodes[0]), "x");
+    let bold = element(&nodes[1]);
+    assert_eq!(element(&bold.children[0]).tag, HtmlTag::I);
+    assert_eq!(text(&nodes[2]), "z");
+
+    // A tag that never ends is text
+    assert_eq!(text(&parse_html_label("a <b")[1]), "<b");
+}
+
+#[test]
+fn only_angle_bracket_labels_are_html() {
+    let graph = parse_graph(
+        r#"digraph {
+            h [label=<<TABLE><TR><TD PORT="p">&amp;</TD></TR></TABLE>>];
+            q [label="<b>not html</b>"];
+        }"#,
+    )
+    .unwrap();
+    let table = element(&graph.nodes[0].html_label.as_ref().unwrap()[0]);
+    assert_eq!(table.tag, HtmlTag::Table);
+    assert!(graph.nodes[1].html_label.is_none());
+    assert_eq!(graph.nodes[1].label.as_deref(), Some("<b>not html</b>"));
+}
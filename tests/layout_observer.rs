@@ -0,0 +1,49 @@
+//! Client rects converted into an arena's own coordinate space, and node ids scoped to it
+use dioxus_plumb::layout_observer::{ArenaSpace, NodeBox, NodeScope};
+
+#[test]
+fn unscaled_arena_offsets_by_its_origin() {
+    let space = ArenaSpace {
+        left: 100.0,
+        top: 50.0,
+        scale_x: 1.0,
+        scale_y: 1.0,
+    };
+    assert_eq!(
+        space.local(130.0, 90.0, 40.0, 20.0),
+        NodeBox {
+            x: 30.0,
+            y: 40.0,
+            width: 40.0,
+            height: 20.0,
+        }
+    );
+}
+
+#[test]
+fn zoomed_arena_boxes_keep_their_layout_size() {
+    // Shown at twice its size
+    let space = ArenaSpace {
+        left: 100.0,
+        top: 50.0,
+        scale_x: 2.0,
+        scale_y: 2.0,
+    };
+    assert_eq!(
+        space.local(160.0, 130.0, 80.0, 40.0),
+        NodeBox {
+            x: 30.0,
+            y: 40.0,
+            width: 40.0,
+            height: 20.0,
+        }
+    );
+}
+
+#[test]
+fn node_scopes_prefix_dom_ids() {
+    let first = NodeScope::new("edge-arena-0-");
+    let second = NodeScope::new("edge-arena-1-");
+    assert_eq!(first.dom_id("A"), "edge-arena-0-A");
+    assert_ne!(first.dom_id("A"), second.dom_id("A"));
+}
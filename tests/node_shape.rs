@@ -0,0 +1,94 @@
+//! Edges end on the outline of a node's shape, not on its bounding box
+use dioxus_plumb::node_shape::NodeShape;
+use dioxus_plumb::perfect_arrows::*;
+use proptest::prelude::*;
+
+const POS: Pos2 = Pos2 { x: 100.0, y: 50.0 };
+const SIZE: Vec2 = Vec2 { x: 120.0, y: 60.0 };
+const CENTER: Pos2 = Pos2 { x: 160.0, y: 80.0 };
+
+// How far `point` is from the outline of `shape` drawn in the box at POS with SIZE,
+// 0 on the outline
+fn outline_distance(shape: NodeShape, point: Pos2) -> f64 {
+    let (dx, dy) = (point.x - CENTER.x, point.y - CENTER.y);
+    let (rx, ry) = (SIZE.x / 2.0, SIZE.y / 2.0);
+    match shape {
+        NodeShape::Ellipse => ((dx / rx).powi(2) + (dy / ry).powi(2)).sqrt() - 1.0,
+        NodeShape::Circle => dx.hypot(dy) / rx.min(ry) - 1.0,
+        NodeShape::Diamond => dx.abs() / rx + dy.abs() / ry - 1.0,
+        NodeShape::Box => (dx.abs() / rx).max(dy.abs() / ry) - 1.0,
+        _ => unreachable!(),
+    }
+}
+
+fn toward(angle: f64, distance: f64) -> Pos2 {
+    Pos2 {
+        x: CENTER.x + angle.cos() * distance,
+        y: CENTER.y + angle.sin() * distance,
+    }
+}
+
+const SHAPES: [NodeShape; 4] = [
+    NodeShape::Ellipse,
+    NodeShape::Circle,
+    NodeShape::Diamond,
+    NodeShape::Box,
+];
+
+proptest! {
+    #[test]
+    fn boundary_points_lie_on_the_outline(angle in 0.0..std::f64::consts::TAU, distance in 1.0..1_000.0) {
+        for shape in SHAPES {
+            let point = shape.outline(POS, SIZE).boundary_point(toward(angle, distance));
+            prop_assert!(outline_distance(shape, point).abs() < 1e-9, "{shape:?} {point:?}");
+            // In the direction that was asked for
+            let hit_angle = (point.y - CENTER.y).atan2(point.x - CENTER.x);
+            let diff = (hit_angle - angle).rem_euclid(std::f64::consts::TAU);
+            prop_assert!(diff.min(std::f64::consts::TAU - diff) < 1e-6, "{shape:?} {point:?}");
+        }
+    }
+}
+
+#[test]
+fn outlines_are_hit_where_expected() {
+    let right = Pos2 { x: 500.0, y: 80.0 };
+    let below = Pos2 { x: 160.0, y: 500.0 };
+    let point = |shape: NodeShape, toward| shape.outline(POS, SIZE).boundary_point(toward);
+
+    assert_eq!(point(NodeShape::Ellipse, right), Pos2 { x: 220.0, y: 80.0 });
+    assert_eq!(
+        point(NodeShape::Ellipse, below),
+        Pos2 { x: 160.0, y: 110.0 }
+    );
+    // A circle keeps to the smaller side of its box
+    assert_eq!(point(NodeShape::Circle, right), Pos2 { x: 190.0, y: 80.0 });
+    assert_eq!(point(NodeShape::Diamond, right), Pos2 { x: 220.0, y: 80.0 });
+
+    // Along the diagonal of the box the diamond is hit halfway to the corner
+    let corner = point(NodeShape::Diamond, Pos2 { x: 220.0, y: 110.0 });
+    assert!((corner.x - 190.0).abs() < 1e-9 && (corner.y - 95.0).abs() < 1e-9);
+}
+
+#[test]
+fn arrows_between_shapes_end_on_both_outlines() {
+    for start_shape in SHAPES {
+        for end_shape in SHAPES {
+            let end_pos = Pos2 { x: 400.0, y: 300.0 };
+            let arrow = get_shape_to_shape_arrow(
+                &start_shape.outline(POS, SIZE),
+                &end_shape.outline(end_pos, SIZE),
+                ArrowOptions::default(),
+            );
+            assert!(outline_distance(start_shape, arrow.start).abs() < 1e-9);
+            // Move the end back next to the first box to measure it the same way
+            let end = Pos2 {
+                x: arrow.end.x - (end_pos.x - POS.x),
+                y: arrow.end.y - (end_pos.y - POS.y),
+            };
+            assert!(
+                outline_distance(end_shape, end).abs() < 1e-9,
+                "{start_shape:?} -> {end_shape:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,211 @@
+//! Property tests: arrow geometry never panics and stays finite for finite input
+use dioxus_plumb::perfect_arrows::*;
+use proptest::prelude::*;
+
+fn coord() -> impl Strategy<Value = f64> {
+    -5_000.0..5_000.0
+}
+
+fn size() -> impl Strategy<Value = f64> {
+    prop_oneof![Just(0.0), 0.0..1_000.0]
+}
+
+fn pos() -> impl Strategy<Value = Pos2> {
+    (coord(), coord()).prop_map(|(x, y)| Pos2 { x, y })
+}
+
+fn vec() -> impl Strategy<Value = Vec2> {
+    (size(), size()).prop_map(|(x, y)| Vec2 { x, y })
+}
+
+fn options() -> impl Strategy<Value = ArrowOptions> {
+    (
+        -1.0..1.0,
+        0.0..1.0,
+        0.0..500.0,
+        0.0..1_000.0,
+        0.0..20.0,
+        0.0..20.0,
+        any::<bool>(),
+        any::<bool>(),
+    )
+        .prop_map(
+            |(bow, stretch, stretch_min, stretch_max, pad_start, pad_end, flip, straights)| {
+                ArrowOptions {
+                    bow,
+                    stretch,
+                    stretch_min,
+                    stretch_max,
+                    pad_start,
+                    pad_end,
+                    flip,
+                    straights,
+                }
+            },
+        )
+}
+
+fn shape() -> impl Strategy<Value = Shape> {
+    prop_oneof![
+        (pos(), vec(), 0.0..50.0).prop_map(|(pos, size, radius)| Shape::Rect { pos, size, radius }),
+        (pos(), vec()).prop_map(|(pos, size)| Shape::Ellipse { pos, size }),
+        (pos(), 0.0..500.0).prop_map(|(center, radius)| Shape::Circle { center, radius }),
+        (pos(), vec()).prop_map(|(pos, size)| Shape::Polygon(vec![
+            Pos2 {
+                x: pos.x + size.x / 2.0,
+                y: pos.y
+            },
+            Pos2 {
+                x: pos.x + size.x,
+                y: pos.y + size.y / 2.0
+            },
+            Pos2 {
+                x: pos.x + size.x / 2.0,
+                y: pos.y + size.y
+            },
+            Pos2 {
+                x: pos.x,
+                y: pos.y + size.y / 2.0
+            },
+        ])),
+    ]
+}
+
+fn assert_finite(arrow: &ArrowGeometry) {
+    for p in [arrow.start, arrow.control, arrow.end] {
+        assert!(p.x.is_finite() && p.y.is_finite(), "{arrow:?}");
+    }
+    for a in [arrow.angle_start, arrow.angle_end, arrow.angle_center] {
+        assert!(a.is_finite(), "{arrow:?}");
+    }
+}
+
+proptest! {
+    #[test]
+    fn box_to_box_is_finite(start in pos(), start_size in vec(), end in pos(), end_size in vec(), options in options()) {
+        assert_finite(&get_box_to_box_arrow(start, start_size, end, end_size, options));
+    }
+
+    #[test]
+    fn identical_centers_are_finite(center in pos(), start_size in vec(), end_size in vec(), options in options()) {
+        let start = Pos2 { x: center.x - start_size.x / 2.0, y: center.y - start_size.y / 2.0 };
+        let end = Pos2 { x: center.x - end_size.x / 2.0, y: center.y - end_size.y / 2.0 };
+        assert_finite(&get_box_to_box_arrow(start, start_size, end, end_size, options));
+    }
+
+    #[test]
+    fn nested_boxes_are_finite(outer in pos(), outer_size in vec(), fx in 0.0..1.0, fy in 0.0..1.0, fw in 0.0..1.0, fh in 0.0..1.0, options in options()) {
+        let inner = Pos2 { x: outer.x + outer_size.x * fx, y: outer.y + outer_size.y * fy };
+        let inner_size = Vec2 { x: (outer.x + outer_size.x - inner.x) * fw, y: (outer.y + outer_size.y - inner.y) * fh };
+        assert_finite(&get_box_to_box_arrow(outer, outer_size, inner, inner_size, options.clone()));
+        assert_finite(&get_box_to_box_arrow(inner, inner_size, outer, outer_size, options));
+    }
+
+    #[test]
+    fn shape_to_shape_is_finite(start in shape(), end in shape(), options in options()) {
+        assert_finite(&get_shape_to_shape_arrow(&start, &end, options));
+    }
+
+    #[test]
+    fn point_arrows_are_finite(start in pos(), end in pos(), end_size in vec(), options in options()) {
+        assert_finite(&get_arrow(start, end, options.clone()));
+        assert_finite(&get_arrow(start, start, options.clone()));
+        assert_finite(&get_point_to_box_arrow(start, end, end_size, options));
+    }
+
+    #[test]
+    fn nan_does_not_panic(start in pos(), size in vec(), options in options()) {
+        let nan = Pos2 { x: f64::NAN, y: start.y };
+        let nan_size = Vec2 { x: size.x, y: f64::NAN };
+        get_box_to_box_arrow(nan, size, start, size, options.clone());
+        get_box_to_box_arrow(start, nan_size, start, size, options.clone());
+        get_arrow(nan, start, options.clone());
+        get_point_to_box_arrow(start, nan, nan_size, options.clone());
+        get_shape_to_shape_arrow(
+            &Shape::Ellipse { pos: start, size: nan_size },
+            &Shape::Polygon(vec![nan, start]),
+            options,
+        );
+    }
+}
+
+fn segment() -> impl Strategy<Value = PathSegment> {
+    prop_oneof![
+        pos().prop_map(|to| PathSegment::Line { to }),
+        (pos(), pos()).prop_map(|(control, to)| PathSegment::Quadratic { control, to }),
+        (pos(), pos(), pos()).prop_map(|(control1, control2, to)| PathSegment::Cubic {
+            control1,
+            control2,
+            to
+        }),
+        (vec(), -3.0..3.0, any::<bool>(), any::<bool>(), pos()).prop_map(
+            |(radii, rotation, large_arc, sweep, to)| PathSegment::Arc {
+                radii,
+                rotation,
+                large_arc,
+                sweep,
+                to
+            }
+        ),
+    ]
+}
+
+fn path() -> impl Strategy<Value = ArrowPath> {
+    (pos(), prop::collection::vec(segment(), 1..4))
+        .prop_map(|(start, segments)| ArrowPath { start, segments })
+}
+
+fn close(a: Pos2, b: Pos2) -> bool {
+    (a.x - b.x).abs() < 1e-6 && (a.y - b.y).abs() < 1e-6
+}
+
+proptest! {
+    #[test]
+    fn path_runs_from_start_to_end(path in path()) {
+        prop_assert!(close(path.point_at(0.0), path.start));
+        prop_assert!(close(path.point_at(1.0), path.end()));
+    }
+
+    #[test]
+    fn path_bounding_box_holds_the_path(path in path()) {
+        let (pos, size) = path.bounding_box();
+        for p in path.flatten(16) {
+            prop_assert!(p.x >= pos.x - 1e-6 && p.x <= pos.x + size.x + 1e-6);
+            prop_assert!(p.y >= pos.y - 1e-6 && p.y <= pos.y + size.y + 1e-6);
+        }
+    }
+
+    #[test]
+    fn path_is_no_shorter_than_its_chord(path in path()) {
+        let Pos2 { x, y } = path.end();
+        let chord = ((x - path.start.x).powi(2) + (y - path.start.y).powi(2)).sqrt();
+        prop_assert!(path.length() >= chord - 1e-6);
+    }
+
+    #[test]
+    fn quadratic_path_follows_the_arrow(start in pos(), start_size in vec(), end in pos(), end_size in vec(), options in options()) {
+        let arrow = get_box_to_box_arrow(start, start_size, end, end_size, options);
+        let path = ArrowPath::from(&arrow);
+        prop_assert!(close(path.start, arrow.start));
+        prop_assert!(close(path.end(), arrow.end));
+        prop_assert!(path.tangent_at(0.5).x.is_finite());
+    }
+}
+
+#[test]
+fn semicircle_arc_has_half_the_circumference() {
+    let path = ArrowPath {
+        start: Pos2 { x: 0.0, y: 0.0 },
+        segments: vec![PathSegment::Arc {
+            radii: Vec2 { x: 50.0, y: 50.0 },
+            rotation: 0.0,
+            large_arc: false,
+            sweep: true,
+            to: Pos2 { x: 100.0, y: 0.0 },
+        }],
+    };
+    assert!((path.length() - std::f64::consts::PI * 50.0).abs() < 0.5);
+    // Sweeping clockwise on screen from the left goes over the top
+    assert!(close(path.point_at(0.5), Pos2 { x: 50.0, y: -50.0 }));
+    assert_eq!(path.to_svg(), "M0,0 A50,50 0 0 1 100,0");
+}
@@ -0,0 +1,117 @@
+//! Ports and compass points dot-parser can't read on its own, and error positions after rewriting them
+use dioxus_plumb::edge_port::{CompassPoint, EdgePort};
+use dioxus_plumb::graph_data::{parse_graph, parse_graph_with_options, ParseOptions};
+
+fn port(name: Option<&str>, compass: Option<CompassPoint>) -> Option<EdgePort> {
+    Some(EdgePort {
+        name: name.map(String::from),
+        compass,
+    })
+}
+
+// Source and target port of the only edge in `dot`
+fn ports(dot: &str) -> (Option<EdgePort>, Option<EdgePort>) {
+    let graph = parse_graph(dot).unwrap();
+    assert_eq!(graph.edges.len(), 1, "{dot}");
+    let edge = &graph.edges[0];
+    (edge.source_port.clone(), edge.target_port.clone())
+}
+
+#[test]
+fn two_letter_compass_points_are_not_split() {
+    for (compass, point) in [
+        ("ne", CompassPoint::NE),
+        ("nw", CompassPoint::NW),
+        ("se", CompassPoint::SE),
+        ("sw", CompassPoint::SW),
+    ] {
+        let dot = format!("digraph {{ a:{compass} -> b:{compass} }}");
+        assert_eq!(
+            ports(&dot),
+            (port(None, Some(point)), port(None, Some(point)))
+        );
+    }
+}
+
+#[test]
+fn ports_keep_their_compass_point() {
+    assert_eq!(
+        ports("digraph { a:p:ne -> b:q:s }"),
+        (
+            port(Some("p"), Some(CompassPoint::NE)),
+            port(Some("q"), Some(CompassPoint::S))
+        )
+    );
+    assert_eq!(
+        ports(r#"digraph { a:"my port":sw -> b:out }"#),
+        (
+            port(Some("my port"), Some(CompassPoint::SW)),
+            port(Some("out"), None)
+        )
+    );
+}
+
+#[test]
+fn compass_lookalikes_stay_port_names() {
+    // Longer identifiers only start with a compass point
+    assert_eq!(
+        ports("digraph { a:new -> b:sea }"),
+        (port(Some("new"), None), port(Some("sea"), None))
+    );
+    assert_eq!(
+        ports("digraph { a:east:w -> b:center:ne }"),
+        (
+            port(Some("east"), Some(CompassPoint::W)),
+            port(Some("center"), Some(CompassPoint::NE))
+        )
+    );
+    // Inside strings and comments nothing is rewritten
+    let graph = parse_graph(
+        r#"digraph {
+            // a:ne
+            /* b:sw */
+            a [label="x:ne"];
+            a -> b;
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(graph.nodes[0].label.as_deref(), Some("x:ne"));
+    assert_eq!(graph.edges[0].source_port, None);
+}
+
+#[test]
+fn html_labels_with_nested_tags_parse() {
+    let graph = parse_graph("digraph { a [label=<<b>bold</b> and <i>x:ne</i>>] }").unwrap();
+    assert!(graph.nodes[0].html_label.is_some());
+}
+
+#[test]
+fn syntax_errors_point_into_the_original_source() {
+    // Each rewrite changes the length of the line before the error
+    for dot in [
+        "digraph {\n  a:ne -> b:p:sw; c -> ; }",
+        "digraph {\n  a [label=<<b>x</b>>]; c -> ; }",
+        "digraph {\n  a [label=\"\\\\\\\\\"]; c -> ; }",
+    ] {
+        let err = parse_graph(dot).unwrap_err();
+        let span = err.span();
+        let semicolon = dot.rfind("; }").unwrap();
+        assert_eq!(span.start, semicolon, "{dot}");
+        assert_eq!(span.line, 2, "{dot}");
+        assert_eq!(
+            span.column,
+            dot[dot.find('\n').unwrap() + 1..semicolon].chars().count() + 1,
+            "{dot}"
+        );
+    }
+}
+
+#[test]
+fn unknown_node_spans_skip_rewritten_ports() {
+    let dot = "digraph { a; a:ne -> missing:sw }";
+    let options = ParseOptions {
+        implicit_nodes: false,
+    };
+    let err = parse_graph_with_options(dot, &options).unwrap_err();
+    assert_eq!(err.span().start, dot.find("missing").unwrap());
+}
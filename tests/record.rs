@@ -0,0 +1,140 @@
+//! Record labels: fields, ports, nested groups and escapes
+use dioxus_plumb::graph_data::parse_graph;
+use dioxus_plumb::record::{parse_record, RecordField};
+
+fn text(port: Option<&str>, text: &str) -> RecordField {
+    RecordField::Text {
+        port: port.map(String::from),
+        text: text.to_string(),
+    }
+}
+
+#[test]
+fn fields_are_split_and_trimmed() {
+    assert_eq!(
+        parse_record(" left |mid dle|  right "),
+        [
+            text(None, "left"),
+            text(None, "mid dle"),
+            text(None, "right")
+        ]
+    );
+    assert_eq!(parse_record(""), [text(None, "")]);
+    assert_eq!(parse_record("a||b")[1], text(None, ""));
+}
+
+#[test]
+fn ports_name_their_field() {
+    assert_eq!(
+        parse_record("<f0> Seqno|< f1 >Prev|<f2>"),
+        [
+            text(Some("f0"), "Seqno"),
+            text(Some("f1"), "Prev"),
+            text(Some("f2"), "")
+        ]
+    );
+}
+
+#[test]
+fn groups_nest() {
+    let fields = parse_record("a|{b|{<c> c|d}|e}|f");
+    assert_eq!(
+        fields,
+        [
+            text(None, "a"),
+            RecordField::Group(vec![
+                text(None, "b"),
+                RecordField::Group(vec![text(Some("c"), "c"), text(None, "d")]),
+                text(None, "e"),
+            ]),
+            text(None, "f"),
+        ]
+    );
+    assert_eq!(fields[1].ports(), ["c"]);
+
+    // The outer braces of a vertical record are a group of their own
+    assert_eq!(
+        parse_record("{ <top> x | y }"),
+        [RecordField::Group(vec![
+            text(Some("top"), "x"),
+            text(None, "y")
+        ])]
+    );
+}
+
+#[test]
+fn unclosed_groups_end_the_label() {
+    assert_eq!(
+        parse_record("{a|b"),
+        [RecordField::Group(vec![text(None, "a"), text(None, "b")])]
+    );
+}
+
+#[test]
+fn stray_closing_braces_are_text() {
+    assert_eq!(
+        parse_record("a|b}|c"),
+        [text(None, "a"), text(None, "b}"), text(None, "c")]
+    );
+    assert_eq!(parse_record("a}|b"), [text(None, "a}"), text(None, "b")]);
+    assert_eq!(
+        parse_record("{a}}|b"),
+        [
+            RecordField::Group(vec![text(None, "a")]),
+            text(None, "}"),
+            text(None, "b")
+        ]
+    );
+}
+
+#[test]
+fn escapes_keep_special_characters() {
+    assert_eq!(
+        parse_record(r"a\|b|\{c\}|\<d\>|e\\f"),
+        [
+            text(None, "a|b"),
+            text(None, "{c}"),
+            text(None, "<d>"),
+            text(None, r"e\f")
+        ]
+    );
+    assert_eq!(
+        parse_record(r"one\ntwo\lthree\r|end\"),
+        [text(None, "one\ntwo\nthree"), text(None, r"end\")]
+    );
+}
+
+#[test]
+fn record_nodes_get_their_fields() {
+    let graph = parse_graph(
+        r#"digraph {
+            s [shape=record, label="<f0> left|{<f1> a\|b|c}"];
+            m [shape=Mrecord];
+            h [shape=record, label=<<b>x|y</b>>];
+            plain [label="a|b"];
+        }"#,
+    )
+    .unwrap();
+    let record = |id: &str| {
+        graph
+            .nodes
+            .iter()
+            .find(|node| node.id == id)
+            .unwrap()
+            .record
+            .clone()
+    };
+
+    let fields = record("s").unwrap();
+    assert_eq!(
+        fields,
+        [
+            text(Some("f0"), "left"),
+            RecordField::Group(vec![text(Some("f1"), "a|b"), text(None, "c")])
+        ]
+    );
+    // Without a label the node id is the only field
+    assert_eq!(record("m").unwrap(), [text(None, "m")]);
+    assert_eq!(record("h"), None);
+    assert_eq!(record("plain"), None);
+}